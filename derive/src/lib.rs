@@ -14,13 +14,25 @@
 /// * The binding is resolved lazily, which results in different methods
 ///     * `bind` has no parameters, is not `async` and always succeeds
 ///     * `read` has the parameters that `bind` would have with `derive(Class)`
-///        that is, `&Process`, `&Module`, `&Image` and optionally an instance
+///       that is, `&Process`, `&Module`, `&Image` and optionally an instance
 ///     * If the class has only static fields, or one of the fields is marked
 ///       as `singleton`, `read` does not take an instance argument.
+///     * `read` returns a `csharp_mem::Error` that describes why the class
+///       could not be read, e.g. a missing class or field or unreadable memory.
+/// * Reference fields of type `csharp_mem::Pointer` may be null, while reading
+///   a null `csharp_mem::NonNullPointer` field fails the whole `read` with
+///   `csharp_mem::Error::NullPointer`.
+/// * `read_checked` reads an instance only if the object is an instance of
+///   the bound class or of one of its subclasses, which guards against
+///   reading a pointer that has been reused for a different object, e.g.
+///   after a scene reload.
+/// * Every field has a `write_<field>` method that writes a value back into
+///   the field through a `csharp_mem::MemWriter`, using the same offsets as
+///   `read`. It takes the writer after the `&Game` and before the instance.
 ///
 /// ### The `rename` attribute is supported on the struct/class level
 ///
-/// ```no_run
+/// ```ignore
 /// #[derive(Class2)]
 /// #[rename = "Timer"]
 /// struct MyTimer {
@@ -34,7 +46,7 @@
 ///
 /// Use two structs instead:
 ///
-/// ```no_run
+/// ```ignore
 /// #[derive(Class2)]
 /// struct Timer {
 ///     #[rename = "currentLevelTime"]
@@ -47,23 +59,48 @@
 ///     #[static_field]
 ///     foo: bool,
 /// }
+/// ```
 ///
 /// ### The binding is resolved lazily
 ///
 /// The class can then be bound to the process like so:
 ///
-/// ```no_run
+/// ```ignore
 /// let timer_class = Timer::bind().await;
 /// ```
 ///
 /// Once you have an instance, you can read the instance from the process like
 /// so:
 ///
-/// ```no_run
+/// ```ignore
 /// if let Ok(timer) = timer_class.read(&process, &module, &image, timer_instance) {
 ///     // Do something with the instance.
 /// }
 /// ```
+///
+/// ### Enums dispatch on the class of the object
+///
+/// Fields that are declared as `object`, an interface or an abstract class
+/// can be read into an enum whose variants each wrap a derived class:
+///
+/// ```ignore
+/// #[derive(Class2)]
+/// enum Item {
+///     Weapon(Weapon),
+///     Potion(Potion),
+/// }
+/// ```
+///
+/// `read_pointer` reads the class of the object and then reads the variant
+/// that binds to that class or, if there is none, to the closest class that
+/// it inherits from. Variants are matched by the name of their class, so the
+/// contents of e.g. a `List<IItem>` can be read without any further setup.
+/// An object that matches no variant fails with
+/// `csharp_mem::Error::UnknownClass`.
+///
+/// Each variant must wrap a single derived class with instance fields,
+/// classes with only static fields or with a `singleton` field cannot be
+/// read from an object and are rejected.
 #[cfg(feature = "il2cpp")]
 #[proc_macro_derive(Il2cppClass, attributes(static_field, singleton, rename))]
 pub fn il2cpp_class_binding(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
@@ -86,13 +123,25 @@ pub fn il2cpp_class_binding(input: proc_macro::TokenStream) -> proc_macro::Token
 /// * The binding is resolved lazily, which results in different methods
 ///     * `bind` has no parameters, is not `async` and always succeeds
 ///     * `read` has the parameters that `bind` would have with `derive(Class)`
-///        that is, `&Process`, `&Module`, `&Image` and optionally an instance
+///       that is, `&Process`, `&Module`, `&Image` and optionally an instance
 ///     * If the class has only static fields, or one of the fields is marked
 ///       as `singleton`, `read` does not take an instance argument.
+///     * `read` returns a `csharp_mem::Error` that describes why the class
+///       could not be read, e.g. a missing class or field or unreadable memory.
+/// * Reference fields of type `csharp_mem::Pointer` may be null, while reading
+///   a null `csharp_mem::NonNullPointer` field fails the whole `read` with
+///   `csharp_mem::Error::NullPointer`.
+/// * `read_checked` reads an instance only if the object is an instance of
+///   the bound class or of one of its subclasses, which guards against
+///   reading a pointer that has been reused for a different object, e.g.
+///   after a scene reload.
+/// * Every field has a `write_<field>` method that writes a value back into
+///   the field through a `csharp_mem::MemWriter`, using the same offsets as
+///   `read`. It takes the writer after the `&Game` and before the instance.
 ///
 /// ### The `rename` attribute is supported on the struct/class level
 ///
-/// ```no_run
+/// ```ignore
 /// #[derive(Class2)]
 /// #[rename = "Timer"]
 /// struct MyTimer {
@@ -106,7 +155,7 @@ pub fn il2cpp_class_binding(input: proc_macro::TokenStream) -> proc_macro::Token
 ///
 /// Use two structs instead:
 ///
-/// ```no_run
+/// ```ignore
 /// #[derive(Class2)]
 /// struct Timer {
 ///     #[rename = "currentLevelTime"]
@@ -119,23 +168,48 @@ pub fn il2cpp_class_binding(input: proc_macro::TokenStream) -> proc_macro::Token
 ///     #[static_field]
 ///     foo: bool,
 /// }
+/// ```
 ///
 /// ### The binding is resolved lazily
 ///
 /// The class can then be bound to the process like so:
 ///
-/// ```no_run
+/// ```ignore
 /// let timer_class = Timer::bind().await;
 /// ```
 ///
 /// Once you have an instance, you can read the instance from the process like
 /// so:
 ///
-/// ```no_run
+/// ```ignore
 /// if let Ok(timer) = timer_class.read(&process, &module, &image, timer_instance) {
 ///     // Do something with the instance.
 /// }
 /// ```
+///
+/// ### Enums dispatch on the class of the object
+///
+/// Fields that are declared as `object`, an interface or an abstract class
+/// can be read into an enum whose variants each wrap a derived class:
+///
+/// ```ignore
+/// #[derive(Class2)]
+/// enum Item {
+///     Weapon(Weapon),
+///     Potion(Potion),
+/// }
+/// ```
+///
+/// `read_pointer` reads the class of the object and then reads the variant
+/// that binds to that class or, if there is none, to the closest class that
+/// it inherits from. Variants are matched by the name of their class, so the
+/// contents of e.g. a `List<IItem>` can be read without any further setup.
+/// An object that matches no variant fails with
+/// `csharp_mem::Error::UnknownClass`.
+///
+/// Each variant must wrap a single derived class with instance fields,
+/// classes with only static fields or with a `singleton` field cannot be
+/// read from an object and are rejected.
 #[cfg(feature = "mono")]
 #[proc_macro_derive(MonoClass, attributes(static_field, singleton, rename))]
 pub fn mono_class_binding(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
@@ -147,7 +221,7 @@ fn process(
     input: proc_macro::TokenStream,
     mono_module: impl quote::ToTokens,
 ) -> proc_macro::TokenStream {
-    match inner::process(input, mono_module) {
+    match inner::process(input.into(), mono_module) {
         Ok(tokens) => tokens.into(),
         Err(e) => e.to_compile_error().into(),
     }
@@ -156,28 +230,29 @@ fn process(
 #[cfg(any(feature = "mono", feature = "il2cpp"))]
 mod inner {
     use proc_macro2::TokenStream;
-    use quote::{quote, ToTokens};
-    use syn::{Attribute, Data, DeriveInput, Expr, ExprLit, Ident, Lit};
+    use quote::{format_ident, quote, ToTokens};
+    use syn::{
+        Attribute, Data, DataEnum, DeriveInput, Expr, ExprLit, Fields, Generics, Ident, Lit, Type,
+    };
 
     struct FieldSpec {
         is_singleton: bool,
         field_name: Ident,
+        field_type: Type,
         binding_name: Ident,
         lookup_name: String,
     }
 
-    pub fn process(
-        input: proc_macro::TokenStream,
-        mono_module: impl ToTokens,
-    ) -> syn::Result<TokenStream> {
-        let ast: DeriveInput = syn::parse(input).unwrap();
+    pub fn process(input: TokenStream, mono_module: impl ToTokens) -> syn::Result<TokenStream> {
+        let ast: DeriveInput = syn::parse2(input)?;
 
         let struct_data = match ast.data {
             Data::Struct(s) => s,
-            _ => {
+            Data::Enum(e) => return enum_binding(ast.ident, ast.generics, e),
+            Data::Union(_) => {
                 return Err(syn::Error::new(
                     ast.ident.span(),
-                    "Only structs are supported.",
+                    "Only structs and enums are supported.",
                 ))
             }
         };
@@ -233,6 +308,7 @@ mod inner {
             let spec = FieldSpec {
                 is_singleton,
                 field_name,
+                field_type: field.ty,
                 binding_name,
                 lookup_name,
             };
@@ -282,14 +358,20 @@ mod inner {
         .map(
             |FieldSpec {
                  field_name,
+                 field_type,
                  binding_name,
                  lookup_name,
                  ..
              }| {
                 FieldDef {
+                    is_singleton: false,
                     name: field_name,
+                    field_type,
                     typ: quote! { ::core::option::Option<asr::Address>},
-                    lookup: quote! { class.get_static_field(game.process(), game.module(), #lookup_name)? },
+                    lookup: quote! {
+                        class.get_static_field(game.process(), game.module(), #lookup_name)
+                            .ok_or(::csharp_mem::Error::FieldNotFound { class: #lookup_class, field: #lookup_name })?
+                    },
                     read: quote! { #binding_name },
                     binding: binding_name,
                 }
@@ -318,7 +400,7 @@ mod inner {
 
         fields.sort_by_key(|o| !o.is_singleton);
 
-        let singleton_name = fields.get(0).and_then(|o| {
+        let singleton_name = fields.first().and_then(|o| {
             o.is_singleton
                 .then(|| Ident::new(SINGLETON_NAME, o.field_name.span()))
         });
@@ -329,26 +411,37 @@ mod inner {
             |FieldSpec {
                  is_singleton,
                  field_name,
+                 field_type,
                  binding_name,
                  lookup_name,
             }| {
                 if is_singleton {
                     let name = singleton_name.as_ref().unwrap();
                     FieldDef {
+                        is_singleton,
                         name: field_name,
+                        field_type,
                         typ: quote! { ::core::option::Option<asr::Address> },
-                        lookup: quote! { class.get_static_field(game.process(), game.module(), #lookup_name)? },
+                        lookup: quote! {
+                            class.get_static_field(game.process(), game.module(), #lookup_name)
+                                .ok_or(::csharp_mem::Error::FieldNotFound { class: #lookup_class, field: #lookup_name })?
+                        },
                         read: quote! { #name },
                         binding: name.clone(),
                     }
                 } else {
 
                     FieldDef {
+                        is_singleton,
                         name: field_name,
+                        field_type,
                         typ: quote! { ::core::option::Option<::core::num::NonZeroU32> },
                         lookup: quote! {
-                            ::core::num::NonZeroU32::new(class.get_field_offset(game.process(), game.module(), #lookup_name)?)
-                                .expect("A field with offset 0 in a unity project is not valid")
+                            ::core::num::NonZeroU32::new(
+                                class.get_field_offset(game.process(), game.module(), #lookup_name)
+                                    .ok_or(::csharp_mem::Error::FieldNotFound { class: #lookup_class, field: #lookup_name })?
+                            )
+                            .expect("A field with offset 0 in a unity project is not valid")
                         },
                         read: match singleton_name.as_ref() {
                             Some(instance) =>quote! { ::asr::Address::from(#instance) + #binding_name.get() },
@@ -378,7 +471,11 @@ mod inner {
     }
 
     struct FieldDef {
+        is_singleton: bool,
         name: Ident,
+        /// The type of the field in the struct.
+        field_type: Type,
+        /// The type of the resolved binding of the field.
         typ: TokenStream,
         lookup: TokenStream,
         read: TokenStream,
@@ -393,32 +490,150 @@ mod inner {
         additional_params: TokenStream,
         fields2: Vec<FieldDef>,
     ) -> TokenStream {
+        let class_lookup = quote! {
+            let class = match self.class {
+                ::core::option::Option::Some(ref cls) => cls,
+                ::core::option::Option::None => {
+                    let class = game.image().get_class(game.process(), game.module(), #lookup_class)
+                        .ok_or(::csharp_mem::Error::ClassNotFound { class: #lookup_class })?;
+                    self.class = ::core::option::Option::Some(class);
+                    self.class.as_ref().unwrap()
+                }
+            };
+        };
+
+        let resolve = |field: &FieldDef| {
+            let FieldDef {
+                name,
+                lookup,
+                binding,
+                ..
+            } = field;
+            quote! {
+                let #binding = match self.#name {
+                    ::core::option::Option::Some(field) => field,
+                    ::core::option::Option::None => {
+                        let field = #lookup;
+                        self.#name = ::core::option::Option::Some(field);
+                        field
+                    }
+                };
+            }
+        };
+
+        // The singleton is always the first field, see `non_static_binding`.
+        let singleton = fields2.first().filter(|o| o.is_singleton);
+
+        let write_impls = fields2
+            .iter()
+            .map(|field| {
+                let method = format_ident!("write_{}", field.name);
+                let field_type = &field.field_type;
+                let address = &field.read;
+                let resolve_instance = match singleton {
+                    Some(singleton) if !field.is_singleton => {
+                        let instance = &singleton.binding;
+                        let instance_type = &singleton.field_type;
+                        let resolve = resolve(singleton);
+                        quote! {
+                            #resolve
+                            let #instance = ::csharp_mem::MemReader::read::<#instance_type, _>(&*writer, #instance)?;
+                        }
+                    }
+                    _ => quote! {},
+                };
+                let resolve = resolve(field);
+                quote! {
+                    pub fn #method<__M: ::csharp_mem::MemWriter>(
+                        &mut self,
+                        game: &::csharp_mem::Game<'_>,
+                        writer: &mut __M,
+                        #additional_params
+                        value: #field_type,
+                    ) -> ::core::result::Result<(), ::csharp_mem::Error>
+                    where
+                        // The higher-ranked bound turns a field type that is
+                        // not `NoUninit` into an uncallable method instead of
+                        // a compile error.
+                        for<'__a> #field_type: ::csharp_mem::__bytemuck::NoUninit,
+                    {
+                        #class_lookup
+                        #resolve_instance
+                        #resolve
+                        ::csharp_mem::MemWriter::write(writer, #address, value)
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+
         let mut field_names = Vec::new();
         let mut field_types = Vec::new();
         let mut binding_names = Vec::new();
-        let mut lookups = Vec::new();
+        let mut resolves = Vec::new();
         let mut reads = Vec::new();
 
         for field in fields2 {
+            resolves.push(resolve(&field));
+            let address = field.read;
+            reads.push(if is_non_null_pointer(&field.field_type) {
+                let field_type = field.field_type;
+                quote! { <#field_type>::__read_field(game, #address)? }
+            } else {
+                quote! { ::csharp_mem::MemReader::read(game, #address)? }
+            });
             field_names.push(field.name);
             field_types.push(field.typ);
             binding_names.push(field.binding);
-            lookups.push(field.lookup);
-            reads.push(field.read);
         }
 
-        let read_pointer = if additional_params.is_empty() {
-            quote! {}
+        let (read_pointer, instance_class) = if additional_params.is_empty() {
+            (quote! {}, quote! {})
         } else {
-            quote! {
-                pub fn read_pointer(
+            let read_pointer = quote! {
+                pub fn read_pointer<W: ::csharp_mem::PointerWidth>(
                     &mut self,
                     game: &::csharp_mem::Game<'_>,
-                    pointer: ::csharp_mem::Pointer<#struct_name>,
-                ) -> Option<#struct_name> {
-                    self.read(game, pointer.address().into())
+                    pointer: ::csharp_mem::Pointer<#struct_name, W>,
+                ) -> ::core::result::Result<#struct_name, ::csharp_mem::Error> {
+                    self.read(game, pointer.into())
                 }
-            }
+
+                pub fn read_checked<W: ::csharp_mem::PointerWidth>(
+                    &mut self,
+                    game: &::csharp_mem::Game<'_>,
+                    pointer: ::csharp_mem::Pointer<#struct_name, W>,
+                ) -> ::core::result::Result<#struct_name, ::csharp_mem::Error> {
+                    let actual = pointer.class_id(game)?;
+                    if actual.find_ancestor(game, #lookup_class)?.is_none() {
+                        return ::core::result::Result::Err(::csharp_mem::Error::UnexpectedClass {
+                            address: pointer.into(),
+                            expected: #lookup_class,
+                            actual: actual.address(),
+                        });
+                    }
+                    self.read(game, pointer.into())
+                }
+            };
+            let instance_class = quote! {
+                impl ::csharp_mem::__InstanceClass for #struct_name {
+                    type Binding = #generate_struct;
+
+                    const CLASS_NAME: &'static str = #lookup_class;
+
+                    fn bind() -> #generate_struct {
+                        #struct_name::bind()
+                    }
+
+                    fn read(
+                        binding: &mut #generate_struct,
+                        game: &::csharp_mem::Game<'_>,
+                        instance: ::asr::Address,
+                    ) -> ::core::result::Result<Self, ::csharp_mem::Error> {
+                        binding.read(game, instance)
+                    }
+                }
+            };
+            (read_pointer, instance_class)
         };
 
         let read_impl = if field_names.is_empty() {
@@ -429,32 +644,16 @@ mod inner {
                     &mut self,
                     game: &::csharp_mem::Game<'_>,
                     #additional_params
-                ) -> ::core::option::Option<#struct_name> {
-                    let class = match self.class {
-                        ::core::option::Option::Some(ref cls) => cls,
-                        ::core::option::Option::None => {
-                            let class = game.image().get_class(game.process(), game.module(), #lookup_class)?;
-                            self.class = ::core::option::Option::Some(class);
-                            self.class.as_ref().unwrap()
-                        }
-                    };
+                ) -> ::core::result::Result<#struct_name, ::csharp_mem::Error> {
+                    #class_lookup
 
-                    #(
-                        let #binding_names = match self.#field_names {
-                            ::core::option::Option::Some(field) => field,
-                            ::core::option::Option::None => {
-                                let field = #lookups;
-                                self.#field_names = ::core::option::Option::Some(field);
-                                field
-                            }
-                        };
-                    )*
+                    #(#resolves)*
 
                     #(
-                        let #binding_names = game.process().read(#reads).map_err(drop).ok()?;
+                        let #binding_names = #reads;
                     )*
 
-                    ::core::option::Option::Some(#struct_name {#(#field_names: #binding_names,)*})
+                    ::core::result::Result::Ok(#struct_name {#(#field_names: #binding_names,)*})
                 }
             }
         };
@@ -469,22 +668,17 @@ mod inner {
                 pub fn class(
                     &mut self,
                     game: &::csharp_mem::Game<'_>,
-                ) -> ::core::option::Option<&#mono_module::Class> {
-                    let class = match self.class {
-                        ::core::option::Option::Some(ref cls) => cls,
-                        ::core::option::Option::None => {
-                            let class = game.image().get_class(game.process(), game.module(), #lookup_class)?;
-                            self.class = ::core::option::Option::Some(class);
-                            self.class.as_ref().unwrap()
-                        }
-                    };
+                ) -> ::core::result::Result<&#mono_module::Class, ::csharp_mem::Error> {
+                    #class_lookup
 
-                    ::core::option::Option::Some(class)
+                    ::core::result::Result::Ok(class)
                 }
 
                 #read_impl
 
                 #read_pointer
+
+                #(#write_impls)*
             }
 
             impl #struct_name {
@@ -495,8 +689,111 @@ mod inner {
                     }
                 }
             }
+
+            #instance_class
+        }
+    }
+
+    fn enum_binding(
+        enum_name: Ident,
+        generics: Generics,
+        data: DataEnum,
+    ) -> syn::Result<TokenStream> {
+        if !generics.params.is_empty() {
+            return Err(syn::Error::new_spanned(
+                generics,
+                "Enums with generic parameters are not supported.",
+            ));
+        }
+        if data.variants.is_empty() {
+            return Err(syn::Error::new(
+                enum_name.span(),
+                "Enums must have at least one variant.",
+            ));
+        }
+
+        let binding_name = format_ident!("{enum_name}Binding");
+
+        let mut variants = Vec::new();
+        let mut bindings = Vec::new();
+        let mut class_types = Vec::new();
+        let mut indices = Vec::new();
+
+        for (index, variant) in data.variants.into_iter().enumerate() {
+            let class_type = match variant.fields {
+                Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                    fields.unnamed.into_iter().next().unwrap().ty
+                }
+                _ => return Err(syn::Error::new(
+                    variant.ident.span(),
+                    "Enum variants must have exactly one unnamed field that is a derived class.",
+                )),
+            };
+
+            bindings.push(format_ident!("__internal_variant_{index}"));
+            class_types.push(class_type);
+            variants.push(variant.ident);
+            indices.push(index);
+        }
+
+        Ok(quote! {
+            struct #binding_name {
+                #(#bindings: <#class_types as ::csharp_mem::__InstanceClass>::Binding,)*
+            }
+
+            impl #binding_name {
+                pub fn read_pointer<W: ::csharp_mem::PointerWidth>(
+                    &mut self,
+                    game: &::csharp_mem::Game<'_>,
+                    pointer: ::csharp_mem::Pointer<#enum_name, W>,
+                ) -> ::core::result::Result<#enum_name, ::csharp_mem::Error> {
+                    const CLASS_NAMES: &[&str] = &[
+                        #(<#class_types as ::csharp_mem::__InstanceClass>::CLASS_NAME,)*
+                    ];
+                    let class = pointer.class_id(game)?;
+                    match class.find_ancestor_name(game, CLASS_NAMES)? {
+                        #(
+                            ::core::option::Option::Some(#indices) => {
+                                ::core::result::Result::Ok(#enum_name::#variants(
+                                    <#class_types as ::csharp_mem::__InstanceClass>::read(
+                                        &mut self.#bindings,
+                                        game,
+                                        pointer.into(),
+                                    )?,
+                                ))
+                            }
+                        )*
+                        _ => ::core::result::Result::Err(::csharp_mem::Error::UnknownClass {
+                            address: pointer.into(),
+                            class: class.address(),
+                        }),
+                    }
+                }
+            }
+
+            impl #enum_name {
+                fn bind() -> #binding_name {
+                    #binding_name {
+                        #(#bindings: <#class_types as ::csharp_mem::__InstanceClass>::bind(),)*
+                    }
+                }
+            }
+        })
+    }
+
+    /// A null `NonNullPointer` is read separately to report it as a null
+    /// pointer instead of an invalid bit pattern.
+    fn is_non_null_pointer(field_type: &Type) -> bool {
+        match field_type {
+            Type::Path(path) if path.qself.is_none() => path
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "NonNullPointer"),
+            _ => false,
         }
     }
+
     fn parse_rename(attr: &Attribute) -> Option<syn::Result<String>> {
         attr.path()
             .is_ident("rename")
@@ -514,4 +811,138 @@ mod inner {
             })
             .flatten()
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn error(input: TokenStream) -> String {
+            process(input, quote! { mono })
+                .map(|_| ())
+                .unwrap_err()
+                .to_string()
+        }
+
+        #[test]
+        fn enum_variants_are_read_through_their_class() {
+            let tokens = process(
+                quote! {
+                    enum Item {
+                        Weapon(items::Weapon),
+                        HealthPotion(HealthPotion),
+                    }
+                },
+                quote! { mono },
+            )
+            .unwrap()
+            .to_string();
+            assert!(tokens.contains(
+                "const CLASS_NAMES : & [& str] = & [< items :: Weapon as :: csharp_mem :: __InstanceClass > :: CLASS_NAME , < HealthPotion as :: csharp_mem :: __InstanceClass > :: CLASS_NAME ,] ;"
+            ));
+            assert!(tokens.contains("__internal_variant_1 : < HealthPotion as :: csharp_mem :: __InstanceClass > :: Binding"));
+            // The nearest ancestor of the class that is named like a variant
+            // selects it, so an instance of a subclass of `HealthPotion` is
+            // read as a `HealthPotion`.
+            assert!(tokens.contains("match class . find_ancestor_name (game , CLASS_NAMES) ?"));
+            assert!(tokens.contains(
+                ":: core :: option :: Option :: Some (1usize) => { :: core :: result :: Result :: Ok (Item :: HealthPotion (< HealthPotion as :: csharp_mem :: __InstanceClass > :: read (& mut self . __internal_variant_1 ,"
+            ));
+            assert!(tokens.contains(
+                "_ => :: core :: result :: Result :: Err (:: csharp_mem :: Error :: UnknownClass"
+            ));
+        }
+
+        #[test]
+        fn unsupported_enums_are_rejected() {
+            assert_eq!(
+                error(quote! { enum Item<T> { Weapon(T) } }),
+                "Enums with generic parameters are not supported."
+            );
+            assert_eq!(
+                error(quote! { enum Item {} }),
+                "Enums must have at least one variant."
+            );
+            for variant in [
+                quote! { Weapon },
+                quote! { Weapon { weapon: Weapon } },
+                quote! { Weapon(Weapon, Potion) },
+            ] {
+                assert_eq!(
+                    error(quote! { enum Item { #variant } }),
+                    "Enum variants must have exactly one unnamed field that is a derived class."
+                );
+            }
+        }
+
+        #[test]
+        fn null_non_null_pointers_are_null_pointers() {
+            let tokens = process(
+                quote! {
+                    struct Holder {
+                        target: csharp_mem::NonNullPointer<Player>,
+                        maybe: Pointer<Player>,
+                    }
+                },
+                quote! { mono },
+            )
+            .unwrap()
+            .to_string();
+            assert!(tokens.contains(
+                "let __internal_field_target = < csharp_mem :: NonNullPointer < Player > > :: __read_field"
+            ));
+            assert!(
+                tokens.contains("let __internal_field_maybe = :: csharp_mem :: MemReader :: read")
+            );
+        }
+
+        #[test]
+        fn every_field_has_a_write_method() {
+            let instance = process(
+                quote! {
+                    struct Player {
+                        hp: u32,
+                        #[rename = "maxHp"]
+                        max_hp: u32,
+                    }
+                },
+                quote! { mono },
+            )
+            .unwrap()
+            .to_string();
+            for method in ["pub fn write_hp", "pub fn write_max_hp"] {
+                assert!(instance.contains(method), "{method} is missing");
+            }
+            assert!(instance.contains("instance : :: asr :: Address , value : u32"));
+
+            let statics = process(
+                quote! {
+                    struct Config {
+                        #[static_field]
+                        volume: f32,
+                    }
+                },
+                quote! { mono },
+            )
+            .unwrap()
+            .to_string();
+            assert!(statics.contains("pub fn write_volume"));
+            assert!(statics.contains("writer : & mut __M , value : f32"));
+        }
+
+        #[test]
+        fn only_instance_classes_can_be_variants() {
+            let instance = process(quote! { struct Weapon { damage: u32 } }, quote! { mono })
+                .unwrap()
+                .to_string();
+            assert!(instance.contains("impl :: csharp_mem :: __InstanceClass for Weapon"));
+
+            for class in [
+                quote! { struct Config { #[static_field] volume: f32 } },
+                quote! { struct Player { #[singleton] instance: Address, hp: u32 } },
+            ] {
+                let tokens = process(class, quote! { mono }).unwrap().to_string();
+                assert!(!tokens.contains("__InstanceClass"));
+            }
+        }
+    }
 }
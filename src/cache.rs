@@ -0,0 +1,244 @@
+//! Caching of memory reads for the duration of a tick.
+//!
+//! Splitters often read the same memory several times during one
+//! update, e.g. a singleton, the header of a `List` and then its items.
+//! A [`CachedReader`] reads whole pages from the wrapped [`MemReader`]
+//! and serves all following reads that fall into those pages from its
+//! cache, until it is [invalidated](CachedReader::invalidate).
+//!
+//! ```
+//! use csharp_mem::{cache::CachedReader, fake::FakeHeap};
+//!
+//! let mut heap = FakeHeap::new();
+//! let scores = heap.list(0x1000, 0x1100, &[10_u32, 20, 30]);
+//!
+//! let mut reader = CachedReader::new(&heap);
+//!
+//! // Both reads are served by reading the page at 0x1000 only once.
+//! assert_eq!(scores.get(&reader, 0).unwrap(), 10);
+//! assert_eq!(scores.get(&reader, 2).unwrap(), 30);
+//!
+//! // The memory might change until the next tick.
+//! reader.invalidate();
+//! ```
+
+use alloc::{boxed::Box, collections::BTreeMap, vec::Vec};
+use core::cell::RefCell;
+
+use asr::Address;
+use bytemuck::CheckedBitPattern;
+
+use crate::{Backend, BackendReader, Error, MemReader};
+
+const PAGE_SIZE: u64 = 0x1000;
+
+type Page = Box<[u8; PAGE_SIZE as usize]>;
+
+/// A [`MemReader`] that caches the memory of the wrapped reader
+/// in page sized blocks until it is [invalidated](Self::invalidate).
+pub struct CachedReader<R> {
+    reader: R,
+    cache: RefCell<Cache>,
+}
+
+#[derive(Default)]
+struct Cache {
+    /// `None` for pages that could not be read.
+    pages: BTreeMap<u64, Option<Page>>,
+    /// Allocations of invalidated pages, ready to be reused.
+    spare: Vec<Page>,
+}
+
+impl<R: MemReader> CachedReader<R> {
+    /// Create a new reader with an empty cache.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            cache: RefCell::new(Cache::default()),
+        }
+    }
+
+    /// Returns the reader whose reads are cached.
+    pub const fn reader(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns the wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Drop all cached memory, so that the following reads
+    /// see the current state of the memory.
+    /// This should be called once per tick.
+    pub fn invalidate(&mut self) {
+        let Cache { pages, spare } = self.cache.get_mut();
+        spare.extend(core::mem::take(pages).into_values().flatten());
+    }
+
+    fn copy_to(&self, addr: Address, buf: &mut [u8]) -> bool {
+        let mut cache = self.cache.borrow_mut();
+        let Cache { pages, spare } = &mut *cache;
+
+        let mut addr = addr.value();
+        let mut buf = buf;
+        while !buf.is_empty() {
+            let index = addr / PAGE_SIZE;
+            let page = pages.entry(index).or_insert_with(|| {
+                let mut page = spare
+                    .pop()
+                    .unwrap_or_else(|| Box::new([0; PAGE_SIZE as usize]));
+                match self
+                    .reader
+                    .read_bytes(Address::new(index * PAGE_SIZE), &mut page[..])
+                {
+                    Ok(()) => Some(page),
+                    Err(_) => {
+                        spare.push(page);
+                        None
+                    }
+                }
+            });
+            let Some(page) = page else {
+                return false;
+            };
+
+            let offset = (addr % PAGE_SIZE) as usize;
+            let len = buf.len().min(PAGE_SIZE as usize - offset);
+            buf[..len].copy_from_slice(&page[offset..offset + len]);
+            buf = &mut buf[len..];
+            addr += len as u64;
+        }
+        true
+    }
+}
+
+impl<R: MemReader> MemReader for CachedReader<R> {
+    fn read<T: CheckedBitPattern, A: Into<Address>>(&self, addr: A) -> Result<T, Error> {
+        let addr = addr.into();
+        crate::read_bits(addr, |buf| {
+            self.read_bytes(addr, buf)
+                .map_err(|_| Error::unreadable::<T>(addr))
+        })
+    }
+
+    fn read_bytes<A: Into<Address>>(&self, addr: A, buf: &mut [u8]) -> Result<(), Error> {
+        let addr = addr.into();
+        if self.copy_to(addr, buf) {
+            Ok(())
+        } else {
+            // Not every reader can read whole pages, e.g. a replay of a
+            // recording, so try to read only what was asked for.
+            self.reader.read_bytes(addr, buf)
+        }
+    }
+
+    fn read_into<T: CheckedBitPattern, A: Into<Address>>(
+        &self,
+        addr: A,
+        buf: &mut [T],
+    ) -> Result<(), Error> {
+        crate::read_into_chunked(self, addr, buf)
+    }
+
+    fn max_len(&self) -> usize {
+        self.reader.max_len()
+    }
+}
+
+impl<R: BackendReader> BackendReader for CachedReader<R> {
+    fn backend(&self) -> Backend {
+        self.reader.backend()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fake::FakeHeap,
+        record::{Recorder, Replay},
+        stats::StatsReader,
+    };
+
+    /// Lets the heap change while a [`CachedReader`] reads from it.
+    impl MemReader for RefCell<FakeHeap> {
+        fn read<T: CheckedBitPattern, A: Into<Address>>(&self, addr: A) -> Result<T, Error> {
+            self.borrow().read(addr)
+        }
+
+        fn read_bytes<A: Into<Address>>(&self, addr: A, buf: &mut [u8]) -> Result<(), Error> {
+            self.borrow().read_bytes(addr, buf)
+        }
+    }
+
+    #[test]
+    fn reads_of_a_cached_page_are_hits() {
+        let mut heap = FakeHeap::new();
+        heap.write(0x1000_u64, 1_u32).write(0x1ff0_u64, 2_u64);
+
+        let reader = CachedReader::new(StatsReader::new(&heap));
+        assert_eq!(reader.read::<u32, _>(0x1000_u64).unwrap(), 1);
+        assert_eq!(reader.read::<u32, _>(0x1000_u64).unwrap(), 1);
+        assert_eq!(reader.read::<u64, _>(0x1ff0_u64).unwrap(), 2);
+
+        let total = reader.reader().summary().total;
+        assert_eq!(total.reads, 1);
+        assert_eq!(total.bytes, PAGE_SIZE);
+    }
+
+    #[test]
+    fn invalidate_drops_cached_pages() {
+        let heap = RefCell::new(FakeHeap::new());
+        heap.borrow_mut().write(0x1000_u64, 1_u32);
+
+        let mut reader = CachedReader::new(&heap);
+        assert_eq!(reader.read::<u32, _>(0x1000_u64).unwrap(), 1);
+        heap.borrow_mut().write(0x1000_u64, 2_u32);
+        assert_eq!(reader.read::<u32, _>(0x1000_u64).unwrap(), 1);
+
+        reader.invalidate();
+        assert_eq!(reader.read::<u32, _>(0x1000_u64).unwrap(), 2);
+    }
+
+    #[test]
+    fn reads_across_pages_are_joined() {
+        let mut heap = FakeHeap::new();
+        let bytes = (0..16).collect::<Vec<u8>>();
+        heap.write_bytes(0x1ff8_u64, &bytes);
+
+        let reader = CachedReader::new(StatsReader::new(&heap));
+        let mut buf = [0; 16];
+        reader.read_bytes(0x1ff8_u64, &mut buf).unwrap();
+        assert_eq!(buf[..], bytes[..]);
+        assert_eq!(
+            reader.read::<u64, _>(0x1ffc_u64).unwrap(),
+            u64::from_le_bytes([4, 5, 6, 7, 8, 9, 10, 11])
+        );
+        assert_eq!(reader.reader().summary().total.reads, 2);
+
+        // A read fails if any of its pages cannot be read.
+        assert!(reader.read::<u64, _>(0x2ffc_u64).is_err());
+    }
+
+    #[test]
+    fn unreadable_pages_fall_back_to_the_wrapped_reader() {
+        let mut heap = FakeHeap::new();
+        heap.write(0x1000_u64, 1_u32).write(0x1008_u64, 2_u32);
+        // A replay only knows the reads that were recorded,
+        // so it cannot read the page as a whole.
+        let recorder = Recorder::new(&heap);
+        assert_eq!(recorder.read::<u32, _>(0x1000_u64).unwrap(), 1);
+        let replay = Replay::new(recorder.into_recording());
+
+        let reader = CachedReader::new(StatsReader::new(replay));
+        assert_eq!(reader.read::<u32, _>(0x1000_u64).unwrap(), 1);
+        assert_eq!(reader.read::<u32, _>(0x1000_u64).unwrap(), 1);
+        assert!(reader.read::<u32, _>(0x1008_u64).is_err());
+
+        // The page is only tried once, every read falls back.
+        let total = reader.reader().summary().total;
+        assert_eq!(total.reads, 4);
+        assert_eq!(total.failed, 2);
+    }
+}
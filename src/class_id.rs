@@ -0,0 +1,613 @@
+use core::{fmt, hash, marker::PhantomData, mem::size_of};
+
+use asr::{
+    arrayvec::{ArrayString, ArrayVec},
+    Address,
+};
+use bytemuck::CheckedBitPattern;
+
+use crate::{Bit64, Error, MemReader, Pointer, PointerWidth};
+
+/// The scripting backend of a Unity game.
+///
+/// It determines how an object refers to its class and
+/// how the metadata of a class is laid out.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// Objects start with a pointer to their vtable,
+    /// which in turn starts with a pointer to the class.
+    Mono,
+    /// Objects start with a pointer to their class.
+    Il2cpp,
+}
+
+impl Backend {
+    /// The offsets of the parent and the name of a class.
+    const fn class_layout<W: PointerWidth>(self) -> (u64, u64) {
+        let bit64 = size_of::<W::Address>() == 8;
+        match (self, bit64) {
+            (Self::Mono, true) => (0x30, 0x48),
+            (Self::Mono, false) => (0x20, 0x2C),
+            (Self::Il2cpp, true) => (0x58, 0x10),
+            (Self::Il2cpp, false) => (0x2C, 0x8),
+        }
+    }
+}
+
+/// A [`MemReader`] that knows the [`Backend`] of the game that it reads from,
+/// which is required to find the class of an object.
+pub trait BackendReader: MemReader {
+    /// Returns the scripting backend of the game.
+    fn backend(&self) -> Backend;
+}
+
+impl<R: BackendReader> BackendReader for &R {
+    fn backend(&self) -> Backend {
+        (**self).backend()
+    }
+}
+
+/// A [`MemReader`] that adds a [`Backend`] to a reader that does not know it,
+/// e.g. a [`FakeHeap`](crate::fake::FakeHeap) or a
+/// [`Replay`](crate::record::Replay).
+pub struct WithBackend<R> {
+    reader: R,
+    backend: Backend,
+}
+
+impl<R: MemReader> WithBackend<R> {
+    /// Wrap the reader and treat it as a game with the given backend.
+    pub const fn new(reader: R, backend: Backend) -> Self {
+        Self { reader, backend }
+    }
+
+    /// Returns the wrapped reader.
+    pub const fn reader(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns the wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: MemReader> MemReader for WithBackend<R> {
+    fn read<T: CheckedBitPattern, A: Into<Address>>(&self, addr: A) -> Result<T, Error> {
+        self.reader.read(addr)
+    }
+
+    fn read_bytes<A: Into<Address>>(&self, addr: A, buf: &mut [u8]) -> Result<(), Error> {
+        self.reader.read_bytes(addr, buf)
+    }
+
+    fn read_into<T: CheckedBitPattern, A: Into<Address>>(
+        &self,
+        addr: A,
+        buf: &mut [T],
+    ) -> Result<(), Error> {
+        self.reader.read_into(addr, buf)
+    }
+
+    fn max_len(&self) -> usize {
+        self.reader.max_len()
+    }
+}
+
+impl<R: MemReader> BackendReader for WithBackend<R> {
+    fn backend(&self) -> Backend {
+        self.backend
+    }
+}
+
+/// The identity of the class of a managed object,
+/// which is the address of its class metadata.
+///
+/// The metadata of a class does not move while the game is running,
+/// so two objects are instances of the same class if they have the same
+/// [`ClassId`]. The name and the parent of the class are read from its
+/// metadata, which allows checking that a pointer still points to an
+/// instance of a class or one of its subclasses, e.g. after a scene reload.
+#[derive(Copy, Clone)]
+pub struct ClassId<W: PointerWidth = Bit64> {
+    address: Address,
+    _width: PhantomData<W>,
+}
+
+/// The maximum number of parents that are followed before the
+/// chain of parents is considered to be garbage.
+const MAX_DEPTH: usize = 64;
+
+/// The number of bytes of a class name that are read at once.
+const NAME_CHUNK: usize = 16;
+
+/// Reads of a class name never cross a page, so a name at the end of
+/// a mapping can be read even though the chunk would not fit.
+const PAGE_SIZE: u64 = 0x1000;
+
+impl<W: PointerWidth> ClassId<W> {
+    /// Read the class of the object at the given address.
+    pub fn of<R: BackendReader>(reader: &R, object: impl Into<Address>) -> Result<Self, Error> {
+        let object = object.into();
+        if object.is_null() {
+            return Err(Error::null_pointer::<Self>());
+        }
+        let mut field = object;
+        let mut class: Address = reader.read::<W::Address, _>(field)?.into();
+        if reader.backend() == Backend::Mono && !class.is_null() {
+            // Mono objects point to their vtable, which points to the class.
+            field = class;
+            class = reader.read::<W::Address, _>(field)?.into();
+        }
+        Self::from_address(class).ok_or(Error::null_pointer_at::<Self>(field))
+    }
+
+    fn from_address(address: Address) -> Option<Self> {
+        (!address.is_null()).then_some(Self {
+            address,
+            _width: PhantomData,
+        })
+    }
+
+    /// Returns the address of the class metadata.
+    pub const fn address(self) -> Address {
+        self.address
+    }
+
+    /// Read the name of the class, without its namespace.
+    pub fn name<R: BackendReader, const CAP: usize>(
+        self,
+        reader: &R,
+    ) -> Result<ArrayString<CAP>, Error> {
+        let name = self.name_address(reader)?;
+        let mut bytes = ArrayVec::<u8, CAP>::new();
+        let mut truncated = false;
+        read_c_str(reader, name, |chunk| {
+            truncated = bytes.try_extend_from_slice(chunk).is_err();
+            !truncated
+        })?;
+        if truncated {
+            return Err(Error::Truncated {
+                address: name,
+                capacity: CAP,
+            });
+        }
+        let name_str = core::str::from_utf8(&bytes).map_err(|_| Error::InvalidBitPattern {
+            address: name,
+            size: bytes.len(),
+            type_name: core::any::type_name::<str>(),
+        })?;
+        Ok(ArrayString::from(name_str).expect("the name has been checked against the capacity"))
+    }
+
+    /// Check whether the name of the class, without its namespace, is `name`.
+    pub fn has_name<R: BackendReader>(self, reader: &R, name: &str) -> Result<bool, Error> {
+        Ok(self.find_name(reader, &[name])?.is_some())
+    }
+
+    /// Find the first of `names` that is the name of the class, without its
+    /// namespace, and return its index. The name is only read once, no
+    /// matter how many names it is compared with.
+    pub fn find_name<R: BackendReader>(
+        self,
+        reader: &R,
+        names: &[&str],
+    ) -> Result<Option<usize>, Error> {
+        // The first name that starts with what has been read so far.
+        // The names after it match as well if they share its prefix.
+        let mut first = (!names.is_empty()).then_some(0);
+        let mut read = 0;
+        read_c_str(reader, self.name_address(reader)?, |chunk| {
+            let Some(current) = first else {
+                return false;
+            };
+            let prefix = &names[current].as_bytes()[..read];
+            let end = read + chunk.len();
+            first = (current..names.len()).find(|&index| {
+                let name = names[index].as_bytes();
+                name.get(..read) == Some(prefix) && name.get(read..end) == Some(chunk)
+            });
+            read = end;
+            first.is_some()
+        })?;
+        Ok(first.and_then(|current| {
+            let name = &names[current].as_bytes()[..read];
+            (current..names.len()).find(|&index| names[index].as_bytes() == name)
+        }))
+    }
+
+    /// Read the class that this class directly inherits from.
+    /// Returns `None` for `System.Object`, which has no parent.
+    pub fn parent<R: BackendReader>(self, reader: &R) -> Result<Option<Self>, Error> {
+        let (parent, _) = reader.backend().class_layout::<W>();
+        let parent = reader.read::<W::Address, _>(self.address + parent)?;
+        Ok(Self::from_address(parent.into()))
+    }
+
+    /// Iterate over this class and all classes that it inherits from,
+    /// starting with this class and ending with `System.Object`.
+    ///
+    /// The iteration fails with [`Error::TooLong`] if the chain of
+    /// parents is implausibly long, e.g. because it contains a cycle.
+    pub fn ancestors<R: BackendReader>(self, reader: &R) -> Ancestors<'_, R, W> {
+        Ancestors {
+            reader,
+            start: self.address,
+            next: Some(self),
+            depth: 0,
+        }
+    }
+
+    /// Check whether this class is `class` or inherits from it.
+    pub fn is_subclass_of<R: BackendReader>(self, reader: &R, class: Self) -> Result<bool, Error> {
+        for ancestor in self.ancestors(reader) {
+            if ancestor? == class {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Find the first class in the [ancestors](Self::ancestors)
+    /// of this class with the given name.
+    pub fn find_ancestor<R: BackendReader>(
+        self,
+        reader: &R,
+        name: &str,
+    ) -> Result<Option<Self>, Error> {
+        for ancestor in self.ancestors(reader) {
+            let ancestor = ancestor?;
+            if ancestor.has_name(reader, name)? {
+                return Ok(Some(ancestor));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Find the first class in the [ancestors](Self::ancestors) of this
+    /// class whose name is one of `names`, like [`find_name`](Self::find_name),
+    /// and return the index of its name.
+    pub fn find_ancestor_name<R: BackendReader>(
+        self,
+        reader: &R,
+        names: &[&str],
+    ) -> Result<Option<usize>, Error> {
+        for ancestor in self.ancestors(reader) {
+            if let Some(index) = ancestor?.find_name(reader, names)? {
+                return Ok(Some(index));
+            }
+        }
+        Ok(None)
+    }
+
+    fn name_address<R: BackendReader>(self, reader: &R) -> Result<Address, Error> {
+        let (_, name) = reader.backend().class_layout::<W>();
+        let field = self.address + name;
+        let name: Address = reader.read::<W::Address, _>(field)?.into();
+        if name.is_null() {
+            Err(Error::null_pointer_at::<str>(field))
+        } else {
+            Ok(name)
+        }
+    }
+}
+
+/// Calls `f` with consecutive chunks of the NUL terminated string at the
+/// address, without the terminator, until the string ends or `f` returns
+/// `false`.
+fn read_c_str<R: MemReader>(
+    reader: &R,
+    address: Address,
+    mut f: impl FnMut(&[u8]) -> bool,
+) -> Result<(), Error> {
+    let mut cursor = address;
+    loop {
+        let len = NAME_CHUNK.min((PAGE_SIZE - cursor.value() % PAGE_SIZE) as usize);
+        let mut chunk = [0; NAME_CHUNK];
+        let chunk = &mut chunk[..len];
+        reader.read_bytes(cursor, chunk)?;
+        if let Some(end) = chunk.iter().position(|&b| b == 0) {
+            f(&chunk[..end]);
+            return Ok(());
+        }
+        if !f(chunk) {
+            return Ok(());
+        }
+        cursor = cursor + len as u64;
+    }
+}
+
+// The following are manual implementations and not derived because the
+// derive macros would require `W` to implement them as well, which
+// `PointerWidth` does not, so generic code could not use them.
+impl<W: PointerWidth> fmt::Debug for ClassId<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ClassId").field(&self.address).finish()
+    }
+}
+
+impl<W: PointerWidth> PartialEq for ClassId<W> {
+    fn eq(&self, other: &Self) -> bool {
+        self.address == other.address
+    }
+}
+
+impl<W: PointerWidth> Eq for ClassId<W> {}
+
+impl<W: PointerWidth> hash::Hash for ClassId<W> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.address.hash(state);
+    }
+}
+
+/// An iterator over a class and the classes that it inherits from,
+/// see [`ClassId::ancestors`].
+pub struct Ancestors<'r, R, W: PointerWidth> {
+    reader: &'r R,
+    start: Address,
+    next: Option<ClassId<W>>,
+    depth: usize,
+}
+
+impl<R: BackendReader, W: PointerWidth> Iterator for Ancestors<'_, R, W> {
+    type Item = Result<ClassId<W>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let class = self.next.take()?;
+        if self.depth == MAX_DEPTH {
+            return Some(Err(Error::TooLong {
+                address: self.start,
+                len: self.depth + 1,
+                max_len: MAX_DEPTH,
+            }));
+        }
+        self.depth += 1;
+        match class.parent(self.reader) {
+            Ok(parent) => {
+                self.next = parent;
+                Some(Ok(class))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+impl<T, W: PointerWidth> Pointer<T, W> {
+    /// Read the class of the object that this pointer points to.
+    pub fn class_id<R: BackendReader>(self, reader: &R) -> Result<ClassId<W>, Error> {
+        ClassId::of(reader, self)
+    }
+
+    /// Read the name of the class of the object that this pointer points to,
+    /// without its namespace.
+    pub fn class_name<R: BackendReader, const CAP: usize>(
+        self,
+        reader: &R,
+    ) -> Result<ArrayString<CAP>, Error> {
+        self.class_id(reader)?.name(reader)
+    }
+
+    /// Check that this pointer points to an instance of the given class
+    /// or of one of its subclasses.
+    pub fn is_instance_of<R: BackendReader>(
+        self,
+        reader: &R,
+        class: ClassId<W>,
+    ) -> Result<bool, Error> {
+        self.class_id(reader)?.is_subclass_of(reader, class)
+    }
+
+    /// Check that this pointer points to an instance of the class with
+    /// the given name, without its namespace, or of one of its subclasses.
+    pub fn is_instance_of_name<R: BackendReader>(
+        self,
+        reader: &R,
+        name: &str,
+    ) -> Result<bool, Error> {
+        Ok(self
+            .class_id(reader)?
+            .find_ancestor(reader, name)?
+            .is_some())
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use crate::{fake::FakeHeap, Bit32};
+
+    const OBJECT: u64 = 0x1000;
+    const VTABLE: u64 = 0x2000;
+    const OBJECT_CLASS: u64 = 0x3000;
+    const ENEMY_CLASS: u64 = 0x3100;
+    const BOSS_CLASS: u64 = 0x3200;
+    const NAMES: u64 = 0x4000;
+
+    /// Lay out the class metadata of `Boss : Enemy : Object` and
+    /// an instance of `Boss`.
+    fn game<W: PointerWidth>(backend: Backend) -> WithBackend<FakeHeap<W>> {
+        let mut heap = FakeHeap::<W>::with_width();
+        let (parent, name) = backend.class_layout::<W>();
+        let classes = [
+            (OBJECT_CLASS, 0, "Object"),
+            (ENEMY_CLASS, OBJECT_CLASS, "Enemy"),
+            (BOSS_CLASS, ENEMY_CLASS, "Boss"),
+        ];
+        for (index, (class, parent_class, class_name)) in classes.into_iter().enumerate() {
+            let name_addr = NAMES + 0x10 * index as u64;
+            heap.write_bytes(name_addr, class_name.as_bytes())
+                .write(name_addr + class_name.len() as u64, 0_u8)
+                .write(class + parent, W::from_address(parent_class.into()))
+                .write(class + name, W::from_address(name_addr.into()));
+        }
+        match backend {
+            Backend::Mono => heap
+                .write(OBJECT, W::from_address(VTABLE.into()))
+                .write(VTABLE, W::from_address(BOSS_CLASS.into())),
+            Backend::Il2cpp => heap.write(OBJECT, W::from_address(BOSS_CLASS.into())),
+        };
+        WithBackend::new(heap, backend)
+    }
+
+    fn class<W: PointerWidth>(address: u64) -> ClassId<W> {
+        ClassId::from_address(address.into()).unwrap()
+    }
+
+    fn check_instance<W: PointerWidth>(backend: Backend) {
+        let game = game::<W>(backend);
+        let boss = Pointer::<(), W>::new(W::from_address(OBJECT.into()));
+
+        assert_eq!(boss.class_id(&game).unwrap(), class(BOSS_CLASS));
+        assert_eq!(boss.class_name::<_, 16>(&game).unwrap().as_str(), "Boss");
+        let ancestors = boss
+            .class_id(&game)
+            .unwrap()
+            .ancestors(&game)
+            .collect::<Result<alloc::vec::Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            ancestors,
+            [class(BOSS_CLASS), class(ENEMY_CLASS), class(OBJECT_CLASS)]
+        );
+
+        assert!(boss.is_instance_of(&game, class(BOSS_CLASS)).unwrap());
+        assert!(boss.is_instance_of(&game, class(ENEMY_CLASS)).unwrap());
+        assert!(boss.is_instance_of_name(&game, "Enemy").unwrap());
+        assert!(!boss.is_instance_of_name(&game, "Enem").unwrap());
+        assert!(!boss.is_instance_of_name(&game, "Enemy2").unwrap());
+
+        let boss = boss.class_id(&game).unwrap();
+        assert_eq!(
+            boss.find_name(&game, &["Bo", "Bosses", "Boss"]),
+            Ok(Some(2))
+        );
+        assert_eq!(boss.find_name(&game, &["Enemy", "Object"]), Ok(None));
+        assert_eq!(boss.find_name(&game, &[]), Ok(None));
+        // The nearest ancestor wins, regardless of the order of the names.
+        assert_eq!(
+            boss.find_ancestor_name(&game, &["Object", "Enemy", "Player"]),
+            Ok(Some(1))
+        );
+        assert_eq!(boss.find_ancestor_name(&game, &["Player"]), Ok(None));
+        assert!(!class::<W>(ENEMY_CLASS)
+            .is_subclass_of(&game, class(BOSS_CLASS))
+            .unwrap());
+    }
+
+    #[test]
+    fn mono_64() {
+        check_instance::<Bit64>(Backend::Mono);
+    }
+
+    #[test]
+    fn mono_32() {
+        check_instance::<Bit32>(Backend::Mono);
+    }
+
+    #[test]
+    fn il2cpp_64() {
+        check_instance::<Bit64>(Backend::Il2cpp);
+    }
+
+    #[test]
+    fn il2cpp_32() {
+        check_instance::<Bit32>(Backend::Il2cpp);
+    }
+
+    #[test]
+    fn long_names_are_truncated() {
+        let game = game::<Bit64>(Backend::Il2cpp);
+        assert_eq!(
+            class::<Bit64>(OBJECT_CLASS).name::<_, 5>(&game),
+            Err(Error::Truncated {
+                address: NAMES.into(),
+                capacity: 5,
+            })
+        );
+        assert_eq!(
+            class::<Bit64>(OBJECT_CLASS)
+                .name::<_, 6>(&game)
+                .unwrap()
+                .as_str(),
+            "Object"
+        );
+    }
+
+    #[test]
+    fn names_at_the_end_of_a_page_are_read() {
+        let mut heap = game::<Bit64>(Backend::Il2cpp).into_inner();
+        let name = 0x5FFA_u64;
+        heap.write_bytes(name, b"Bomb\0")
+            .write(BOSS_CLASS + 0x10, name);
+        let game = WithBackend::new(heap, Backend::Il2cpp);
+
+        let boss = class::<Bit64>(BOSS_CLASS);
+        assert_eq!(boss.name::<_, 16>(&game).unwrap().as_str(), "Bomb");
+        assert!(boss.has_name(&game, "Bomb").unwrap());
+    }
+
+    #[test]
+    fn long_names_are_compared_in_chunks() {
+        let mut heap = game::<Bit64>(Backend::Il2cpp).into_inner();
+        let name = 0x5000_u64;
+        heap.write_bytes(name, b"FinalBossOfTheSecondWorld\0")
+            .write(BOSS_CLASS + 0x10, name);
+        let game = WithBackend::new(heap, Backend::Il2cpp);
+
+        let boss = class::<Bit64>(BOSS_CLASS);
+        let names = [
+            "FinalBossOfTheFirstWorld",
+            "FinalBossOfTheSecondWorl",
+            "FinalBossOfTheSecondWorlds",
+            "FinalBossOfTheSecondWorld",
+        ];
+        assert_eq!(boss.find_name(&game, &names), Ok(Some(3)));
+        assert_eq!(boss.find_name(&game, &names[..3]), Ok(None));
+        assert!(boss.has_name(&game, names[3]).unwrap());
+        assert!(!boss.has_name(&game, names[1]).unwrap());
+        // The derived dispatch falls back to the parent of the class.
+        assert_eq!(
+            boss.find_ancestor_name(&game, &["Player", "Enemy"]),
+            Ok(Some(1))
+        );
+    }
+
+    #[test]
+    fn cyclic_parents_are_rejected() {
+        let mut heap = game::<Bit64>(Backend::Il2cpp).into_inner();
+        heap.write(OBJECT_CLASS + 0x58, BOSS_CLASS);
+        let game = WithBackend::new(heap, Backend::Il2cpp);
+
+        let boss = class::<Bit64>(BOSS_CLASS);
+        assert_eq!(
+            boss.is_subclass_of(&game, class(0x9000)),
+            Err(Error::TooLong {
+                address: BOSS_CLASS.into(),
+                len: MAX_DEPTH + 1,
+                max_len: MAX_DEPTH,
+            })
+        );
+    }
+
+    #[test]
+    fn null_objects_have_no_class() {
+        let game = game::<Bit64>(Backend::Mono);
+        assert_eq!(
+            Pointer::<(), Bit64>::new(asr::Address64::NULL).class_id(&game),
+            Err(Error::null_pointer::<ClassId<Bit64>>())
+        );
+
+        // The error points at the field that should have held the class.
+        let mut heap = FakeHeap::new();
+        heap.write(OBJECT, VTABLE).write(VTABLE, 0_u64);
+        assert_eq!(
+            ClassId::<Bit64>::of(&WithBackend::new(&heap, Backend::Mono), OBJECT),
+            Err(Error::null_pointer_at::<ClassId<Bit64>>(VTABLE))
+        );
+        heap.write(OBJECT, 0_u64);
+        assert_eq!(
+            ClassId::<Bit64>::of(&WithBackend::new(&heap, Backend::Il2cpp), OBJECT),
+            Err(Error::null_pointer_at::<ClassId<Bit64>>(OBJECT))
+        );
+    }
+}
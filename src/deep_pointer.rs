@@ -0,0 +1,283 @@
+use core::{fmt, marker::PhantomData};
+
+use asr::{arrayvec::ArrayVec, Address};
+use bytemuck::{CheckedBitPattern, NoUninit};
+
+use crate::{Bit64, Error, MemReader, MemWriter, Pointer, PointerWidth};
+
+/// A path to a value that is reached by following a chain of pointers.
+///
+/// The path starts at the address of an object and every hop adds an
+/// offset to the current address. All hops but the last one then read
+/// a pointer from that address and continue at the object it points to,
+/// while the last hop is the address of the value.
+/// Each pointer along the way is checked for null.
+///
+/// The offsets can either be given directly, or as the names of a class
+/// and one of its fields, which are resolved with [`DeepPointer::resolve`].
+///
+/// ```no_run
+/// use asr::Address;
+/// use csharp_mem::{DeepPointer, Error, MemReader};
+///
+/// // instance -> player -> stats -> hp
+/// fn hp(reader: &impl MemReader, instance: Address) -> Result<i32, Error> {
+///     DeepPointer::<i32>::new(instance)
+///         .offset(0x18)
+///         .offset(0x20)
+///         .offset(0x10)
+///         .read(reader)
+/// }
+/// ```
+pub struct DeepPointer<T, W: PointerWidth = Bit64, const CAP: usize = 8> {
+    base: Address,
+    hops: ArrayVec<Hop, CAP>,
+    _t: PhantomData<(T, W)>,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum Hop {
+    Offset(u64),
+    Field {
+        class: &'static str,
+        field: &'static str,
+        offset: Option<u32>,
+    },
+}
+
+impl<T, W: PointerWidth, const CAP: usize> DeepPointer<T, W, CAP> {
+    /// Start a path at the object at the given address.
+    pub fn new(base: impl Into<Address>) -> Self {
+        Self {
+            base: base.into(),
+            hops: ArrayVec::new(),
+            _t: PhantomData,
+        }
+    }
+
+    /// Start a path at the object that the pointer points to.
+    pub fn from_pointer<U>(pointer: Pointer<U, W>) -> Self {
+        Self::new(pointer)
+    }
+
+    /// Start a path at the object whose pointer is stored at the given
+    /// address, e.g. the address of a static field.
+    pub fn from_static(address: impl Into<Address>) -> Self {
+        Self::new(address).offset(0)
+    }
+
+    /// Add a hop with the given offset to the path.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the path already has `CAP` hops.
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.hops.push(Hop::Offset(offset));
+        self
+    }
+
+    /// Add a hop to the field of the given class to the path.
+    /// The offset of the field needs to be resolved with
+    /// [`DeepPointer::resolve`] before the path can be followed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the path already has `CAP` hops.
+    pub fn field(mut self, class: &'static str, field: &'static str) -> Self {
+        self.hops.push(Hop::Field {
+            class,
+            field,
+            offset: None,
+        });
+        self
+    }
+
+    /// Resolve the offsets of all fields in the path that have not
+    /// been resolved yet.
+    #[cfg(any(feature = "mono", feature = "il2cpp"))]
+    pub fn resolve(&mut self, game: &crate::Game<'_>) -> Result<(), Error> {
+        for hop in &mut self.hops {
+            if let Hop::Field {
+                class,
+                field,
+                offset: offset @ None,
+            } = hop
+            {
+                let class_name = *class;
+                let class = game
+                    .image()
+                    .get_class(game.process(), game.module(), class_name)
+                    .ok_or(Error::ClassNotFound { class: class_name })?;
+                let field_offset = class
+                    .get_field_offset(game.process(), game.module(), field)
+                    .ok_or(Error::FieldNotFound {
+                        class: class_name,
+                        field,
+                    })?;
+                *offset = Some(field_offset);
+            }
+        }
+        Ok(())
+    }
+
+    /// Follow all but the last hop of the path and return
+    /// the address of the value.
+    pub fn address<R: MemReader>(&self, reader: &R) -> Result<Address, Error> {
+        let mut address = self.base;
+        for (index, hop) in self.hops.iter().enumerate() {
+            if address.is_null() {
+                return Err(Error::NullPointerInPath { hop: index });
+            }
+            address = address + hop.offset()?;
+            if index + 1 < self.hops.len() {
+                address = reader.read::<W::Address, _>(address)?.into();
+            }
+        }
+        if address.is_null() {
+            return Err(Error::NullPointerInPath {
+                hop: self.hops.len(),
+            });
+        }
+        Ok(address)
+    }
+}
+
+impl<T: CheckedBitPattern, W: PointerWidth, const CAP: usize> DeepPointer<T, W, CAP> {
+    /// Follow the path and read the value at its end.
+    pub fn read<R: MemReader>(&self, reader: &R) -> Result<T, Error> {
+        reader.read(self.address(reader)?)
+    }
+}
+
+impl<T: NoUninit, W: PointerWidth, const CAP: usize> DeepPointer<T, W, CAP> {
+    /// Follow the path and write the value at its end.
+    pub fn write<M: MemWriter>(&self, writer: &mut M, value: T) -> Result<(), Error> {
+        let address = self.address(writer)?;
+        writer.write(address, value)
+    }
+}
+
+impl Hop {
+    fn offset(self) -> Result<u64, Error> {
+        match self {
+            Self::Offset(offset) => Ok(offset),
+            Self::Field {
+                offset: Some(offset),
+                ..
+            } => Ok(offset.into()),
+            Self::Field {
+                class,
+                field,
+                offset: None,
+            } => Err(Error::UnresolvedField { class, field }),
+        }
+    }
+}
+
+impl<T, W: PointerWidth, const CAP: usize> fmt::Debug for DeepPointer<T, W, CAP> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeepPointer")
+            .field("base", &self.base)
+            .field("hops", &self.hops)
+            .field("type", &core::any::type_name::<T>())
+            .finish()
+    }
+}
+
+// This is a manual implementation and not derived because the derive
+// implementation would add a `T: Clone` bound, which is not required.
+impl<T, W: PointerWidth, const CAP: usize> Clone for DeepPointer<T, W, CAP> {
+    fn clone(&self) -> Self {
+        Self {
+            base: self.base,
+            hops: self.hops.clone(),
+            _t: PhantomData,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use crate::{fake::FakeHeap, Bit32};
+
+    /// Lay out `instance -> player -> stats -> hp` with a static field
+    /// that points to the instance.
+    fn heap<W: PointerWidth>() -> FakeHeap<W> {
+        let mut heap = FakeHeap::<W>::with_width();
+        heap.write(0x1018_u64, W::from_address(0x2000_u64.into()))
+            .write(0x2020_u64, W::from_address(0x3000_u64.into()))
+            .write(0x3010_u64, 42_i32)
+            .write(0x4000_u64, W::from_address(0x1000_u64.into()));
+        heap
+    }
+
+    fn hp<W: PointerWidth>(base: DeepPointer<i32, W>) -> DeepPointer<i32, W> {
+        base.offset(0x18).offset(0x20).offset(0x10)
+    }
+
+    fn follow_hops<W: PointerWidth>() {
+        let heap = heap::<W>();
+        let path = hp(DeepPointer::<i32, W>::new(0x1000_u64));
+        assert_eq!(path.address(&heap).unwrap(), Address::new(0x3010));
+        assert_eq!(path.read(&heap).unwrap(), 42);
+    }
+
+    #[test]
+    fn follow_hops_64() {
+        follow_hops::<Bit64>();
+    }
+
+    #[test]
+    fn follow_hops_32() {
+        follow_hops::<Bit32>();
+    }
+
+    #[test]
+    fn null_pointers_report_their_hop() {
+        let mut heap = heap::<Bit64>();
+        heap.write(0x2020_u64, 0_u64);
+        assert_eq!(
+            hp(DeepPointer::<_, Bit64>::new(0x1000_u64)).read(&heap),
+            Err(Error::NullPointerInPath { hop: 2 })
+        );
+        assert_eq!(
+            hp(DeepPointer::<_, Bit64>::new(0_u64)).read(&heap),
+            Err(Error::NullPointerInPath { hop: 0 })
+        );
+    }
+
+    #[test]
+    fn from_static_reads_the_pointer_first() {
+        let heap = heap::<Bit64>();
+        assert_eq!(
+            hp(DeepPointer::<_, Bit64>::from_static(0x4000_u64)).read(&heap),
+            Ok(42)
+        );
+    }
+
+    #[test]
+    fn unresolved_fields_cannot_be_followed() {
+        let heap = heap::<Bit64>();
+        let path = DeepPointer::<i32>::new(0x1000_u64)
+            .offset(0x18)
+            .field("Player", "stats")
+            .offset(0x10);
+        assert_eq!(
+            path.read(&heap),
+            Err(Error::UnresolvedField {
+                class: "Player",
+                field: "stats",
+            })
+        );
+    }
+
+    #[test]
+    fn write_follows_the_path() {
+        let mut heap = heap::<Bit64>();
+        let path = hp(DeepPointer::<_, Bit64>::new(0x1000_u64));
+        path.write(&mut heap, 7).unwrap();
+        assert_eq!(heap.read::<i32, _>(0x3010_u64), Ok(7));
+        assert_eq!(path.read(&heap), Ok(7));
+    }
+}
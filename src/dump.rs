@@ -0,0 +1,482 @@
+//! Reading memory from a dump of a crashed or stopped process.
+//!
+//! A [`Dump`] parses the memory segments of an ELF core file or of a
+//! Windows minidump and serves reads from them, so that a dump that was
+//! captured while the game was running can be inspected offline with the
+//! same [`Pointer`](crate::Pointer) and binding code.
+//!
+//! ```no_run
+//! use asr::Address64;
+//! use csharp_mem::{dump::Dump, CSString, Pointer};
+//!
+//! let dump = Dump::open("game.dmp")?;
+//! let name = Pointer::<CSString>::new(Address64::new(0x7f12_3456_7890));
+//! println!("{}", name.to_std_string(&dump)?);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use asr::Address;
+use bytemuck::CheckedBitPattern;
+
+use crate::{Error, MemReader};
+
+const ELF_MAGIC: [u8; 4] = *b"\x7fELF";
+const MINIDUMP_MAGIC: [u8; 4] = *b"MDMP";
+
+/// A memory dump of a process.
+///
+/// The dump can be backed by anything that can be viewed as bytes,
+/// e.g. a `Vec<u8>` or a memory mapped file.
+pub struct Dump<B> {
+    data: B,
+    format: DumpFormat,
+    /// Sorted by their address.
+    segments: Vec<Segment>,
+}
+
+/// The file format of a [`Dump`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DumpFormat {
+    /// An ELF core file, as written by Linux.
+    Elf,
+    /// A minidump, as written by Windows.
+    Minidump,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Segment {
+    address: u64,
+    offset: usize,
+    len: usize,
+}
+
+impl<B: AsRef<[u8]>> Dump<B> {
+    /// Parse the memory segments of a dump.
+    /// The format is detected by the magic bytes at the start of the data.
+    pub fn parse(data: B) -> Result<Self, DumpError> {
+        let bytes = Bytes(data.as_ref());
+        if bytes.0.len() < ELF_MAGIC.len() {
+            return Err(DumpError::UnknownFormat);
+        }
+        let (format, mut segments) = match bytes.array::<4>(0)? {
+            ELF_MAGIC => (DumpFormat::Elf, elf_segments(bytes)?),
+            MINIDUMP_MAGIC => (DumpFormat::Minidump, minidump_segments(bytes)?),
+            _ => return Err(DumpError::UnknownFormat),
+        };
+        segments.retain(|segment| segment.len != 0);
+        segments.sort_by_key(|segment| segment.address);
+
+        Ok(Self {
+            data,
+            format,
+            segments,
+        })
+    }
+
+    /// Returns the file format of the dump.
+    pub const fn format(&self) -> DumpFormat {
+        self.format
+    }
+
+    /// Returns the start address and the length of all memory
+    /// regions that are contained in the dump.
+    pub fn regions(&self) -> impl Iterator<Item = (Address, u64)> + '_ {
+        self.segments
+            .iter()
+            .map(|segment| (Address::new(segment.address), segment.len as u64))
+    }
+
+    /// Returns the data that backs the dump.
+    pub fn into_inner(self) -> B {
+        self.data
+    }
+
+    fn copy_to(&self, addr: Address, buf: &mut [u8]) -> bool {
+        let data = self.data.as_ref();
+        let mut addr = addr.value();
+        let mut buf = buf;
+        while !buf.is_empty() {
+            // The last segment that starts at or before the address.
+            let index = self
+                .segments
+                .partition_point(|segment| segment.address <= addr);
+            let Some(segment) = index.checked_sub(1).map(|index| self.segments[index]) else {
+                return false;
+            };
+            let offset = addr - segment.address;
+            if offset >= segment.len as u64 {
+                return false;
+            }
+
+            let offset = offset as usize;
+            let len = buf.len().min(segment.len - offset);
+            let start = segment.offset + offset;
+            buf[..len].copy_from_slice(&data[start..start + len]);
+            buf = &mut buf[len..];
+            addr += len as u64;
+        }
+        true
+    }
+}
+
+#[cfg(feature = "std")]
+impl Dump<Vec<u8>> {
+    /// Read and parse the dump file at the given path.
+    pub fn open(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let data = std::fs::read(path)?;
+        Self::parse(data).map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    }
+}
+
+impl<B: AsRef<[u8]>> MemReader for Dump<B> {
+    fn read<T: CheckedBitPattern, A: Into<Address>>(&self, addr: A) -> Result<T, Error> {
+        let addr = addr.into();
+        crate::read_bits(addr, |buf| {
+            if self.copy_to(addr, buf) {
+                Ok(())
+            } else {
+                Err(Error::unreadable::<T>(addr))
+            }
+        })
+    }
+
+    fn read_bytes<A: Into<Address>>(&self, addr: A, buf: &mut [u8]) -> Result<(), Error> {
+        let addr = addr.into();
+        if self.copy_to(addr, buf) {
+            Ok(())
+        } else {
+            Err(Error::unreadable_bytes(addr, buf.len()))
+        }
+    }
+
+    fn read_into<T: CheckedBitPattern, A: Into<Address>>(
+        &self,
+        addr: A,
+        buf: &mut [T],
+    ) -> Result<(), Error> {
+        crate::read_into_chunked(self, addr, buf)
+    }
+}
+
+/// The reason why a [`Dump`] could not be parsed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DumpError {
+    /// The data is neither an ELF file nor a minidump.
+    UnknownFormat,
+    /// The data is an ELF file, but not a little endian core file.
+    UnsupportedFormat,
+    /// The data ended in the middle of a header or a segment.
+    UnexpectedEnd,
+}
+
+impl fmt::Display for DumpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownFormat => f.write_str("the data is neither an ELF file nor a minidump"),
+            Self::UnsupportedFormat => f.write_str("the ELF file is not a little endian core file"),
+            Self::UnexpectedEnd => f.write_str("the dump ended unexpectedly"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DumpError {}
+
+/// Collects the `PT_LOAD` segments of an ELF core file.
+fn elf_segments(bytes: Bytes<'_>) -> Result<Vec<Segment>, DumpError> {
+    const ELFCLASS32: u8 = 1;
+    const ELFCLASS64: u8 = 2;
+    const ELFDATA2LSB: u8 = 1;
+    const ET_CORE: u16 = 4;
+    const PT_LOAD: u32 = 1;
+    const PN_XNUM: u16 = 0xFFFF;
+
+    let is_64 = match bytes.array::<1>(4)?[0] {
+        ELFCLASS32 => false,
+        ELFCLASS64 => true,
+        _ => return Err(DumpError::UnsupportedFormat),
+    };
+    if bytes.array::<1>(5)?[0] != ELFDATA2LSB || bytes.u16(16)? != ET_CORE {
+        return Err(DumpError::UnsupportedFormat);
+    }
+
+    let (phoff, shoff, phentsize, phnum) = if is_64 {
+        (
+            bytes.u64(32)?,
+            bytes.u64(40)?,
+            bytes.u16(54)?,
+            bytes.u16(56)?,
+        )
+    } else {
+        let (phoff, shoff) = (bytes.u32(28)?, bytes.u32(32)?);
+        (phoff.into(), shoff.into(), bytes.u16(42)?, bytes.u16(44)?)
+    };
+    let phnum = if phnum == PN_XNUM {
+        // Too many segments for the header, the real number is stored
+        // in the `sh_info` field of the first section header.
+        bytes.u32(bytes.offset(shoff, if is_64 { 44 } else { 28 })?)?
+    } else {
+        phnum.into()
+    };
+
+    let mut segments = Vec::new();
+    for index in 0..u64::from(phnum) {
+        let header = bytes.offset(phoff, index * u64::from(phentsize))?;
+        if bytes.u32(header)? != PT_LOAD {
+            continue;
+        }
+        let (offset, address, len) = if is_64 {
+            (
+                bytes.u64(header + 8)?,
+                bytes.u64(header + 16)?,
+                bytes.u64(header + 32)?,
+            )
+        } else {
+            (
+                bytes.u32(header + 4)?.into(),
+                bytes.u32(header + 8)?.into(),
+                bytes.u32(header + 16)?.into(),
+            )
+        };
+        // Only the first `p_filesz` bytes are stored in the file,
+        // e.g. read-only segments of mapped files are often left out.
+        segments.push(bytes.segment(address, offset, len)?);
+    }
+    Ok(segments)
+}
+
+/// Collects the memory ranges of the `Memory64ListStream`
+/// and the `MemoryListStream` of a minidump.
+fn minidump_segments(bytes: Bytes<'_>) -> Result<Vec<Segment>, DumpError> {
+    const MEMORY_LIST_STREAM: u32 = 5;
+    const MEMORY64_LIST_STREAM: u32 = 9;
+
+    let streams = bytes.u32(8)?;
+    let directory = bytes.u32(12)?;
+
+    let mut segments = Vec::new();
+    for index in 0..streams {
+        let entry = bytes.offset(directory.into(), u64::from(index) * 12)?;
+        let rva = bytes.u32(entry + 8)? as usize;
+        match bytes.u32(entry)? {
+            MEMORY_LIST_STREAM => {
+                let ranges = bytes.u32(rva)?;
+                for range in 0..ranges {
+                    let descriptor = bytes.offset(rva as u64 + 4, u64::from(range) * 16)?;
+                    let address = bytes.u64(descriptor)?;
+                    let len = bytes.u32(descriptor + 8)?;
+                    let offset = bytes.u32(descriptor + 12)?;
+                    segments.push(bytes.segment(address, offset.into(), len.into())?);
+                }
+            }
+            MEMORY64_LIST_STREAM => {
+                let ranges = bytes.u64(rva)?;
+                // The memory of all ranges is stored back to back.
+                let mut offset = bytes.u64(rva + 8)?;
+                for range in 0..ranges {
+                    let descriptor = bytes.offset(rva as u64 + 16, range * 16)?;
+                    let address = bytes.u64(descriptor)?;
+                    let len = bytes.u64(descriptor + 8)?;
+                    segments.push(bytes.segment(address, offset, len)?);
+                    offset = offset.checked_add(len).ok_or(DumpError::UnexpectedEnd)?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(segments)
+}
+
+#[derive(Copy, Clone)]
+struct Bytes<'a>(&'a [u8]);
+
+impl Bytes<'_> {
+    fn array<const N: usize>(self, offset: usize) -> Result<[u8; N], DumpError> {
+        self.0
+            .get(offset..)
+            .and_then(|bytes| bytes.get(..N))
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(DumpError::UnexpectedEnd)
+    }
+
+    fn u16(self, offset: usize) -> Result<u16, DumpError> {
+        self.array(offset).map(u16::from_le_bytes)
+    }
+
+    fn u32(self, offset: usize) -> Result<u32, DumpError> {
+        self.array(offset).map(u32::from_le_bytes)
+    }
+
+    fn u64(self, offset: usize) -> Result<u64, DumpError> {
+        self.array(offset).map(u64::from_le_bytes)
+    }
+
+    /// Returns `base + offset` as an offset into the data.
+    fn offset(self, base: u64, offset: u64) -> Result<usize, DumpError> {
+        base.checked_add(offset)
+            .and_then(|offset| usize::try_from(offset).ok())
+            .filter(|&offset| offset <= self.0.len())
+            .ok_or(DumpError::UnexpectedEnd)
+    }
+
+    fn segment(self, address: u64, offset: u64, len: u64) -> Result<Segment, DumpError> {
+        let offset = self.offset(offset, 0)?;
+        let len = usize::try_from(len)
+            .ok()
+            .filter(|&len| len <= self.0.len() - offset)
+            .ok_or(DumpError::UnexpectedEnd)?;
+        Ok(Segment {
+            address,
+            offset,
+            len,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `value` at `offset`, growing the data if needed.
+    fn put(data: &mut Vec<u8>, offset: usize, value: &[u8]) {
+        if data.len() < offset + value.len() {
+            data.resize(offset + value.len(), 0);
+        }
+        data[offset..offset + value.len()].copy_from_slice(value);
+    }
+
+    /// A 64 bit core file with a `PT_LOAD` segment of 8 bytes at `0x1000`
+    /// and a `PT_NOTE` segment, which is not memory.
+    fn elf64() -> Vec<u8> {
+        let mut data = Vec::new();
+        put(&mut data, 0, &ELF_MAGIC);
+        put(&mut data, 4, &[2, 1, 1]);
+        put(&mut data, 16, &4_u16.to_le_bytes());
+        put(&mut data, 32, &64_u64.to_le_bytes());
+        put(&mut data, 54, &56_u16.to_le_bytes());
+        put(&mut data, 56, &2_u16.to_le_bytes());
+
+        put(&mut data, 64, &1_u32.to_le_bytes());
+        put(&mut data, 64 + 8, &176_u64.to_le_bytes());
+        put(&mut data, 64 + 16, &0x1000_u64.to_le_bytes());
+        put(&mut data, 64 + 32, &8_u64.to_le_bytes());
+        put(&mut data, 120, &4_u32.to_le_bytes());
+        put(&mut data, 176, &0x1122_3344_5566_7788_u64.to_le_bytes());
+        data
+    }
+
+    /// A 32 bit core file with a `PT_LOAD` segment of 4 bytes at `0x2000`.
+    fn elf32() -> Vec<u8> {
+        let mut data = Vec::new();
+        put(&mut data, 0, &ELF_MAGIC);
+        put(&mut data, 4, &[1, 1, 1]);
+        put(&mut data, 16, &4_u16.to_le_bytes());
+        put(&mut data, 28, &52_u32.to_le_bytes());
+        put(&mut data, 42, &32_u16.to_le_bytes());
+        put(&mut data, 44, &1_u16.to_le_bytes());
+
+        put(&mut data, 52, &1_u32.to_le_bytes());
+        put(&mut data, 52 + 4, &84_u32.to_le_bytes());
+        put(&mut data, 52 + 8, &0x2000_u32.to_le_bytes());
+        put(&mut data, 52 + 16, &4_u32.to_le_bytes());
+        put(&mut data, 84, &0xAABB_CCDD_u32.to_le_bytes());
+        data
+    }
+
+    /// A minidump with a `MemoryListStream` of 4 bytes at `0x3000` and a
+    /// `Memory64ListStream` of two ranges of 2 bytes at `0x4000` and `0x5000`.
+    fn minidump() -> Vec<u8> {
+        let mut data = Vec::new();
+        put(&mut data, 0, &MINIDUMP_MAGIC);
+        put(&mut data, 8, &2_u32.to_le_bytes());
+        put(&mut data, 12, &32_u32.to_le_bytes());
+        // The stream directory.
+        put(&mut data, 32, &5_u32.to_le_bytes());
+        put(&mut data, 32 + 8, &64_u32.to_le_bytes());
+        put(&mut data, 44, &9_u32.to_le_bytes());
+        put(&mut data, 44 + 8, &96_u32.to_le_bytes());
+        // The memory list.
+        put(&mut data, 64, &1_u32.to_le_bytes());
+        put(&mut data, 68, &0x3000_u64.to_le_bytes());
+        put(&mut data, 68 + 8, &4_u32.to_le_bytes());
+        put(&mut data, 68 + 12, &144_u32.to_le_bytes());
+        // The 64 bit memory list.
+        put(&mut data, 96, &2_u64.to_le_bytes());
+        put(&mut data, 104, &148_u64.to_le_bytes());
+        put(&mut data, 112, &0x5000_u64.to_le_bytes());
+        put(&mut data, 120, &2_u64.to_le_bytes());
+        put(&mut data, 128, &0x4000_u64.to_le_bytes());
+        put(&mut data, 136, &2_u64.to_le_bytes());
+        put(&mut data, 144, &[1, 2, 3, 4, 5, 6, 7, 8]);
+        data
+    }
+
+    #[test]
+    fn elf64_core_file() {
+        let dump = Dump::parse(elf64()).unwrap();
+        assert_eq!(dump.format(), DumpFormat::Elf);
+        assert_eq!(
+            dump.regions().collect::<Vec<_>>(),
+            [(Address::new(0x1000), 8)]
+        );
+        assert_eq!(
+            dump.read::<u64, _>(Address::new(0x1000)),
+            Ok(0x1122_3344_5566_7788)
+        );
+        assert_eq!(dump.read::<u16, _>(Address::new(0x1006)), Ok(0x1122));
+        assert!(dump.read::<u16, _>(Address::new(0x1007)).is_err());
+        assert!(dump.read::<u8, _>(Address::new(0xFFF)).is_err());
+    }
+
+    #[test]
+    fn elf32_core_file() {
+        let dump = Dump::parse(elf32()).unwrap();
+        assert_eq!(dump.read::<u32, _>(Address::new(0x2000)), Ok(0xAABB_CCDD));
+    }
+
+    #[test]
+    fn minidump_memory_lists() {
+        let dump = Dump::parse(minidump()).unwrap();
+        assert_eq!(dump.format(), DumpFormat::Minidump);
+        assert_eq!(
+            dump.regions().collect::<Vec<_>>(),
+            [
+                (Address::new(0x3000), 4),
+                (Address::new(0x4000), 2),
+                (Address::new(0x5000), 2),
+            ]
+        );
+        assert_eq!(
+            dump.read::<[u8; 4], _>(Address::new(0x3000)),
+            Ok([1, 2, 3, 4])
+        );
+        assert_eq!(dump.read::<[u8; 2], _>(Address::new(0x5000)), Ok([5, 6]));
+        assert_eq!(dump.read::<[u8; 2], _>(Address::new(0x4000)), Ok([7, 8]));
+    }
+
+    #[test]
+    fn invalid_dumps() {
+        assert_eq!(Dump::parse(b"").err(), Some(DumpError::UnknownFormat));
+        assert_eq!(Dump::parse(b"\x7fEL").err(), Some(DumpError::UnknownFormat));
+        assert_eq!(
+            Dump::parse(b"ZIP!....").err(),
+            Some(DumpError::UnknownFormat)
+        );
+        assert_eq!(
+            Dump::parse(&elf64()[..100]).err(),
+            Some(DumpError::UnexpectedEnd)
+        );
+
+        let mut big_endian = elf64();
+        big_endian[5] = 2;
+        assert_eq!(
+            Dump::parse(big_endian).err(),
+            Some(DumpError::UnsupportedFormat)
+        );
+    }
+}
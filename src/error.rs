@@ -0,0 +1,320 @@
+use core::{fmt, mem::size_of};
+
+use asr::Address;
+
+/// The reason why reading a value from memory failed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// A null [`Pointer`](crate::Pointer) was followed.
+    NullPointer {
+        /// The address of the field that held the null pointer,
+        /// if the pointer was read from one.
+        address: Option<Address>,
+        /// The type that the pointer was pointing to.
+        type_name: &'static str,
+    },
+    /// A null pointer was found while following a
+    /// [`DeepPointer`](crate::DeepPointer).
+    NullPointerInPath {
+        /// The index of the hop that would have followed the null pointer.
+        hop: usize,
+    },
+    /// The memory could not be read, e.g. because it is not mapped.
+    Unreadable {
+        /// The address that was read from.
+        address: Address,
+        /// The number of bytes that were requested.
+        size: usize,
+        /// The type that was requested.
+        type_name: &'static str,
+    },
+    /// The memory could not be written, e.g. because it is not mapped
+    /// or not writable.
+    Unwritable {
+        /// The address that was written to.
+        address: Address,
+        /// The number of bytes that were written.
+        size: usize,
+        /// The type that was written.
+        type_name: &'static str,
+    },
+    /// The memory could be read, but it does not contain a valid
+    /// value of the requested type.
+    InvalidBitPattern {
+        /// The address that was read from.
+        address: Address,
+        /// The number of bytes that were read.
+        size: usize,
+        /// The type that was requested.
+        type_name: &'static str,
+    },
+    /// An index into an array or a list was out of bounds.
+    IndexOutOfBounds {
+        /// The address of the array or list.
+        address: Address,
+        /// The requested index.
+        index: usize,
+        /// The length of the array or list.
+        len: usize,
+    },
+    /// A collection is longer than the maximum length,
+    /// see [`MemReader::max_len`](crate::MemReader::max_len).
+    TooLong {
+        /// The address of the collection.
+        address: Address,
+        /// The length of the collection.
+        len: usize,
+        /// The maximum length.
+        max_len: usize,
+    },
+    /// A string does not fit into the buffer that it is copied into.
+    Truncated {
+        /// The address of the string.
+        address: Address,
+        /// The capacity of the buffer in bytes.
+        capacity: usize,
+    },
+    /// A list or a chunk of a string builder is longer than
+    /// the array that stores its items.
+    InvalidLength {
+        /// The address of the list or the string builder.
+        address: Address,
+        /// The length of the list or the string builder.
+        len: usize,
+        /// The length of the array that stores its items.
+        capacity: usize,
+    },
+    /// An object is not an instance of the expected class.
+    UnexpectedClass {
+        /// The address of the object.
+        address: Address,
+        /// The name of the expected class.
+        expected: &'static str,
+        /// The address of the class of the object.
+        actual: Address,
+    },
+    /// An object is not an instance of any of the classes of
+    /// a derived enum.
+    UnknownClass {
+        /// The address of the object.
+        address: Address,
+        /// The address of the class of the object.
+        class: Address,
+    },
+    /// The class could not be found in the image.
+    ClassNotFound {
+        /// The name of the class.
+        class: &'static str,
+    },
+    /// The field could not be found in the class.
+    FieldNotFound {
+        /// The name of the class.
+        class: &'static str,
+        /// The name of the field.
+        field: &'static str,
+    },
+    /// A [`DeepPointer`](crate::DeepPointer) was followed before
+    /// the offset of one of its fields has been resolved.
+    UnresolvedField {
+        /// The name of the class.
+        class: &'static str,
+        /// The name of the field.
+        field: &'static str,
+    },
+}
+
+impl Error {
+    pub(crate) fn null_pointer<T: ?Sized>() -> Self {
+        Self::NullPointer {
+            address: None,
+            type_name: core::any::type_name::<T>(),
+        }
+    }
+
+    pub(crate) fn null_pointer_at<T: ?Sized>(address: impl Into<Address>) -> Self {
+        Self::NullPointer {
+            address: Some(address.into()),
+            type_name: core::any::type_name::<T>(),
+        }
+    }
+
+    pub(crate) fn unreadable<T>(address: impl Into<Address>) -> Self {
+        Self::Unreadable {
+            address: address.into(),
+            size: size_of::<T>(),
+            type_name: core::any::type_name::<T>(),
+        }
+    }
+
+    pub(crate) fn unreadable_bytes(address: impl Into<Address>, size: usize) -> Self {
+        Self::Unreadable {
+            address: address.into(),
+            size,
+            type_name: core::any::type_name::<[u8]>(),
+        }
+    }
+
+    pub(crate) fn unwritable<T>(address: impl Into<Address>) -> Self {
+        Self::Unwritable {
+            address: address.into(),
+            size: size_of::<T>(),
+            type_name: core::any::type_name::<T>(),
+        }
+    }
+
+    #[cfg_attr(not(feature = "alloc"), allow(dead_code))]
+    pub(crate) fn unwritable_bytes(address: impl Into<Address>, size: usize) -> Self {
+        Self::Unwritable {
+            address: address.into(),
+            size,
+            type_name: core::any::type_name::<[u8]>(),
+        }
+    }
+
+    pub(crate) fn invalid_bit_pattern<T>(address: impl Into<Address>) -> Self {
+        Self::InvalidBitPattern {
+            address: address.into(),
+            size: size_of::<T>(),
+            type_name: core::any::type_name::<T>(),
+        }
+    }
+
+    pub(crate) fn index_out_of_bounds(
+        address: impl Into<Address>,
+        index: usize,
+        len: usize,
+    ) -> Self {
+        Self::IndexOutOfBounds {
+            address: address.into(),
+            index,
+            len,
+        }
+    }
+
+    /// Returns the address at which the failure happened, if there is one.
+    pub const fn address(&self) -> Option<Address> {
+        match *self {
+            Self::Unreadable { address, .. }
+            | Self::Unwritable { address, .. }
+            | Self::InvalidBitPattern { address, .. }
+            | Self::IndexOutOfBounds { address, .. }
+            | Self::TooLong { address, .. }
+            | Self::Truncated { address, .. }
+            | Self::InvalidLength { address, .. }
+            | Self::UnexpectedClass { address, .. }
+            | Self::UnknownClass { address, .. } => Some(address),
+            Self::NullPointer { address, .. } => address,
+            Self::NullPointerInPath { .. }
+            | Self::ClassNotFound { .. }
+            | Self::FieldNotFound { .. }
+            | Self::UnresolvedField { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NullPointer { address, type_name } => {
+                write!(f, "tried to follow a null pointer to `{type_name}`")?;
+                match address {
+                    Some(address) => write!(f, " stored at {:#x}", address.value()),
+                    None => Ok(()),
+                }
+            }
+            Self::NullPointerInPath { hop } => {
+                write!(f, "tried to follow a null pointer at hop {hop} of a path")
+            }
+            Self::Unreadable {
+                address,
+                size,
+                type_name,
+            } => write!(
+                f,
+                "could not read {size} bytes of `{type_name}` at {:#x}",
+                address.value()
+            ),
+            Self::Unwritable {
+                address,
+                size,
+                type_name,
+            } => write!(
+                f,
+                "could not write {size} bytes of `{type_name}` at {:#x}",
+                address.value()
+            ),
+            Self::InvalidBitPattern {
+                address,
+                size,
+                type_name,
+            } => write!(
+                f,
+                "the {size} bytes at {:#x} are not a valid `{type_name}`",
+                address.value()
+            ),
+            Self::IndexOutOfBounds {
+                address,
+                index,
+                len,
+            } => write!(
+                f,
+                "index {index} is out of bounds for the collection at {:#x} with length {len}",
+                address.value()
+            ),
+            Self::TooLong {
+                address,
+                len,
+                max_len,
+            } => write!(
+                f,
+                "the collection at {:#x} has length {len}, which is more than the maximum of {max_len}",
+                address.value()
+            ),
+            Self::Truncated { address, capacity } => write!(
+                f,
+                "the string at {:#x} does not fit into {capacity} bytes",
+                address.value()
+            ),
+            Self::InvalidLength {
+                address,
+                len,
+                capacity,
+            } => write!(
+                f,
+                "the collection at {:#x} has length {len}, but its items only have room for {capacity}",
+                address.value()
+            ),
+            Self::UnexpectedClass {
+                address,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "the object at {:#x} is an instance of the class at {:#x}, which is neither `{expected}` nor a subclass of it",
+                address.value(),
+                actual.value(),
+            ),
+            Self::UnknownClass { address, class } => write!(
+                f,
+                "the object at {:#x} is an instance of the class at {:#x}, which has no variant",
+                address.value(),
+                class.value()
+            ),
+            Self::ClassNotFound { class } => write!(f, "could not find the class `{class}`"),
+            Self::FieldNotFound { class, field } => {
+                write!(
+                    f,
+                    "could not find the field `{field}` in the class `{class}`"
+                )
+            }
+            Self::UnresolvedField { class, field } => write!(
+                f,
+                "the offset of the field `{field}` in the class `{class}` has not been resolved"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
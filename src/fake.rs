@@ -0,0 +1,511 @@
+//! An in-memory fake of a .NET heap.
+//!
+//! [`FakeHeap`] implements [`MemReader`] on top of a sparse byte buffer
+//! and can lay out managed objects in the same shape as a Unity game
+//! would, so that code built on [`Pointer`] and derived classes can be
+//! tested without attaching to a running process.
+//! The heap can lay out objects for 64 bit and for 32 bit processes,
+//! see [`FakeHeap::with_width`].
+//!
+//! ```
+//! use csharp_mem::fake::FakeHeap;
+//!
+//! let mut heap = FakeHeap::new();
+//! let name = heap.string(0x1000, "Level 1");
+//! let scores = heap.list(0x2000, 0x3000, &[10_u32, 20, 30]);
+//!
+//! assert_eq!(name.to_std_string(&heap).unwrap(), "Level 1");
+//! assert_eq!(scores.get(&heap, 2).unwrap(), 30);
+//! ```
+
+use alloc::{boxed::Box, collections::BTreeMap, vec, vec::Vec};
+use core::{
+    marker::PhantomData,
+    mem::{offset_of, size_of},
+};
+
+use asr::Address;
+use bytemuck::{CheckedBitPattern, NoUninit};
+
+use crate::{
+    Array, ArrayBounds, Bit64, Boxed, CSString, Entry, Error, HashCode, List, Map, MemReader,
+    MemWriter, MultiArray, Pointer, PointerWidth, Set, StringBuilder,
+};
+
+const PAGE_SIZE: u64 = 0x1000;
+
+type Page = Box<[u8; PAGE_SIZE as usize]>;
+
+/// A fake .NET heap that keeps its memory in a sparse map of pages,
+/// so that objects can be laid out at any address.
+///
+/// Memory is allocated in pages on the first write to it. Reading from
+/// a page that has never been written to fails, just like reading from
+/// unmapped memory in a real process.
+pub struct FakeHeap<W: PointerWidth = Bit64> {
+    pages: BTreeMap<u64, Page>,
+    _w: PhantomData<W>,
+}
+
+impl FakeHeap {
+    /// Create a new, empty heap for a 64 bit process.
+    pub const fn new() -> Self {
+        Self::with_width()
+    }
+}
+
+impl<W: PointerWidth> Default for FakeHeap<W> {
+    fn default() -> Self {
+        Self::with_width()
+    }
+}
+
+impl<W: PointerWidth> FakeHeap<W> {
+    /// Create a new, empty heap for a process with the pointer width `W`.
+    pub const fn with_width() -> Self {
+        Self {
+            pages: BTreeMap::new(),
+            _w: PhantomData,
+        }
+    }
+
+    /// Write raw bytes to the given address.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bytes would wrap around the end of the address space.
+    pub fn write_bytes(&mut self, addr: impl Into<Address>, bytes: &[u8]) -> &mut Self {
+        let addr = addr.into();
+        if let Err(e) = self.try_write_bytes(addr, bytes) {
+            panic!("{e}");
+        }
+        self
+    }
+
+    fn try_write_bytes(&mut self, addr: Address, bytes: &[u8]) -> Result<(), Error> {
+        // A write that wraps around the address space has nowhere to go.
+        let Some(end) = addr.value().checked_add(bytes.len() as u64) else {
+            return Err(Error::unwritable_bytes(addr, bytes.len()));
+        };
+        let mut addr = addr.value();
+        let mut bytes = bytes;
+        while addr < end {
+            let offset = (addr % PAGE_SIZE) as usize;
+            let len = bytes.len().min(PAGE_SIZE as usize - offset);
+            let page = self
+                .pages
+                .entry(addr / PAGE_SIZE)
+                .or_insert_with(|| Box::new([0; PAGE_SIZE as usize]));
+            page[offset..offset + len].copy_from_slice(&bytes[..len]);
+            bytes = &bytes[len..];
+            addr += len as u64;
+        }
+        Ok(())
+    }
+
+    /// Write a value to the given address.
+    pub fn write<T: NoUninit>(&mut self, addr: impl Into<Address>, value: T) -> &mut Self {
+        self.write_bytes(addr, bytemuck::bytes_of(&value))
+    }
+
+    /// Lay out a `string` at the given address.
+    pub fn string(&mut self, addr: impl Into<Address>, value: &str) -> Pointer<CSString<W>, W> {
+        let addr = addr.into();
+        let units = value.encode_utf16().collect::<Vec<_>>();
+        self.zeroed::<CSString<W>>(addr)
+            .write(
+                addr + offset_of!(CSString<W>, size) as u64,
+                units.len() as u32,
+            )
+            .write_bytes(addr + CSString::<W>::DATA, bytemuck::cast_slice(&units));
+        Pointer::new(W::from_address(addr))
+    }
+
+    /// Lay out a `StringBuilder` at `addr` whose characters are split
+    /// into the given chunks. The other chunks and the arrays of all
+    /// chunks are laid out one after another starting at `chunks_addr`.
+    pub fn string_builder(
+        &mut self,
+        addr: impl Into<Address>,
+        chunks_addr: impl Into<Address>,
+        chunks: &[&str],
+    ) -> Pointer<StringBuilder<W>, W> {
+        let addr = addr.into();
+        let chunks = if chunks.is_empty() { &[""] } else { chunks };
+        let mut next = chunks_addr.into();
+        let mut previous = Address::NULL;
+        let mut offset = 0;
+        for (index, chunk) in chunks.iter().enumerate() {
+            let units = chunk.encode_utf16().collect::<Vec<_>>();
+            let chunk_addr = if index + 1 == chunks.len() {
+                addr
+            } else {
+                let chunk_addr = next;
+                next = next + size_of::<StringBuilder<W>>() as u64;
+                chunk_addr
+            };
+            let chars = self.array(next, &units);
+            next = next + (Array::<u16, W>::DATA + 2 * units.len() as u64).next_multiple_of(8);
+
+            self.zeroed::<StringBuilder<W>>(chunk_addr)
+                .write(
+                    chunk_addr + offset_of!(StringBuilder<W>, chunk_chars) as u64,
+                    chars.address(),
+                )
+                .write(
+                    chunk_addr + offset_of!(StringBuilder<W>, chunk_previous) as u64,
+                    W::from_address(previous),
+                )
+                .write(
+                    chunk_addr + offset_of!(StringBuilder<W>, chunk_length) as u64,
+                    units.len() as u32,
+                )
+                .write(
+                    chunk_addr + offset_of!(StringBuilder<W>, chunk_offset) as u64,
+                    offset,
+                );
+            previous = chunk_addr;
+            offset += units.len() as u32;
+        }
+        Pointer::new(W::from_address(addr))
+    }
+
+    /// Lay out a `T[]` at the given address.
+    pub fn array<T: NoUninit>(
+        &mut self,
+        addr: impl Into<Address>,
+        items: &[T],
+    ) -> Pointer<Array<T, W>, W> {
+        let addr = addr.into();
+        self.array_header::<T>(addr, items.len())
+            .write_bytes(addr + Array::<T, W>::DATA, bytemuck::cast_slice(items));
+        Pointer::new(W::from_address(addr))
+    }
+
+    /// Lay out a multi-dimensional array with the given dimensions at `addr`
+    /// that stores the bounds of its dimensions at `bounds_addr`.
+    /// The items are given in row-major order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of items does not match the dimensions.
+    pub fn multi_array<T: NoUninit, const RANK: usize>(
+        &mut self,
+        addr: impl Into<Address>,
+        bounds_addr: impl Into<Address>,
+        dims: [usize; RANK],
+        items: &[T],
+    ) -> Pointer<MultiArray<T, RANK, W>, W> {
+        assert_eq!(
+            items.len(),
+            dims.iter().product::<usize>(),
+            "the number of items does not match the dimensions"
+        );
+        let addr = addr.into();
+        let bounds_addr = bounds_addr.into();
+
+        for (index, len) in dims.into_iter().enumerate() {
+            let bounds = bounds_addr + (index * size_of::<ArrayBounds<W>>()) as u64;
+            self.zeroed::<ArrayBounds<W>>(bounds).write(
+                bounds + offset_of!(ArrayBounds<W>, length) as u64,
+                W::from_address(Address::new(len as u64)),
+            );
+        }
+
+        self.zeroed::<MultiArray<T, RANK, W>>(addr)
+            .write(
+                addr + offset_of!(MultiArray<T, RANK, W>, bounds) as u64,
+                W::from_address(bounds_addr),
+            )
+            .write(
+                addr + offset_of!(MultiArray<T, RANK, W>, size) as u64,
+                items.len() as u32,
+            )
+            .write_bytes(
+                addr + MultiArray::<T, RANK, W>::DATA,
+                bytemuck::cast_slice(items),
+            );
+        Pointer::new(W::from_address(addr))
+    }
+
+    /// Lay out a `List<T>` at `addr` that stores its items in an array at `items_addr`.
+    pub fn list<T: NoUninit>(
+        &mut self,
+        addr: impl Into<Address>,
+        items_addr: impl Into<Address>,
+        items: &[T],
+    ) -> Pointer<List<T, W>, W> {
+        let addr = addr.into();
+        let array = self.array(items_addr, items);
+        self.zeroed::<List<T, W>>(addr)
+            .write(addr + offset_of!(List<T, W>, items) as u64, array.address())
+            .write(
+                addr + offset_of!(List<T, W>, size) as u64,
+                items.len() as u32,
+            );
+        Pointer::new(W::from_address(addr))
+    }
+
+    /// Lay out a `Dictionary<K, V>` at `addr` that stores its entries
+    /// in an array at `entries_addr`, followed by the array of its buckets.
+    pub fn map<K: NoUninit + HashCode, V: NoUninit>(
+        &mut self,
+        addr: impl Into<Address>,
+        entries_addr: impl Into<Address>,
+        entries: &[(K, V)],
+    ) -> Pointer<Map<K, V, W>, W> {
+        self.map_with_hash(addr, entries_addr, entries, K::hash_code)
+    }
+
+    /// Lay out a `Dictionary<K, V>` like [`map`](Self::map), but with the
+    /// given hash codes, e.g. for keys whose hash code is not known.
+    pub fn map_with_hash<K: NoUninit, V: NoUninit>(
+        &mut self,
+        addr: impl Into<Address>,
+        entries_addr: impl Into<Address>,
+        entries: &[(K, V)],
+        hash_code: impl Fn(&K) -> i32,
+    ) -> Pointer<Map<K, V, W>, W> {
+        self.hash_table(addr, entries_addr, entries, hash_code, 0)
+    }
+
+    /// Lay out a `HashSet<T>` at `addr` that stores its entries
+    /// in an array at `entries_addr`, followed by the array of its buckets.
+    pub fn set<T: NoUninit + HashCode>(
+        &mut self,
+        addr: impl Into<Address>,
+        entries_addr: impl Into<Address>,
+        items: &[T],
+    ) -> Pointer<Set<T, W>, W> {
+        let entries = items.iter().map(|&o| (o, ())).collect::<Vec<_>>();
+        // Unlike `Dictionary`, `HashSet` stores the index plus one
+        // in its buckets, so that a zeroed bucket is empty.
+        let set = Address::from(self.hash_table(addr, entries_addr, &entries, T::hash_code, 1));
+        self.write(
+            set + offset_of!(Set<T, W>, count) as u64,
+            items.len() as u32,
+        )
+        .write(
+            set + offset_of!(Set<T, W>, last_index) as u64,
+            items.len() as u32,
+        );
+        Pointer::new(W::from_address(set))
+    }
+
+    /// Every bucket holds the index of the last entry with its hash code
+    /// plus `first`, and every entry links to the previous one by its index.
+    fn hash_table<K: NoUninit, V: NoUninit>(
+        &mut self,
+        addr: impl Into<Address>,
+        entries_addr: impl Into<Address>,
+        entries: &[(K, V)],
+        hash_code: impl Fn(&K) -> i32,
+        first: i32,
+    ) -> Pointer<Map<K, V, W>, W> {
+        let addr = addr.into();
+        let entries_addr = entries_addr.into();
+
+        let mut buckets = vec![first - 1; entries.len().max(1)];
+        self.array_header::<Entry<K, V>>(entries_addr, entries.len());
+        for (index, (key, value)) in entries.iter().enumerate() {
+            let entry = entries_addr
+                + Array::<Entry<K, V>, W>::DATA
+                + (index * size_of::<Entry<K, V>>()) as u64;
+            let hash = (hash_code(key) & i32::MAX) as u32;
+            let len = buckets.len();
+            let bucket = &mut buckets[hash as usize % len];
+            self.write(entry + offset_of!(Entry<K, V>, hash) as u64, hash)
+                .write(
+                    entry + offset_of!(Entry<K, V>, next) as u64,
+                    *bucket - first,
+                )
+                .write(entry + offset_of!(Entry<K, V>, key) as u64, *key)
+                .write(entry + offset_of!(Entry<K, V>, value) as u64, *value);
+            *bucket = index as i32 + first;
+        }
+
+        let buckets_addr = entries_addr
+            + (Array::<Entry<K, V>, W>::DATA + (entries.len() * size_of::<Entry<K, V>>()) as u64)
+                .next_multiple_of(8);
+        let buckets = self.array(buckets_addr, &buckets);
+
+        self.zeroed::<Map<K, V, W>>(addr)
+            .write(
+                addr + offset_of!(Map<K, V, W>, buckets) as u64,
+                buckets.address(),
+            )
+            .write(
+                addr + offset_of!(Map<K, V, W>, entries) as u64,
+                W::from_address(entries_addr),
+            )
+            .write(
+                addr + offset_of!(Map<K, V, W>, size) as u64,
+                entries.len() as u32,
+            );
+        Pointer::new(W::from_address(addr))
+    }
+
+    /// Lay out a boxed value type at the given address.
+    pub fn boxed<T: NoUninit>(
+        &mut self,
+        addr: impl Into<Address>,
+        value: T,
+    ) -> Pointer<Boxed<T, W>, W> {
+        let addr = addr.into();
+        self.zeroed::<W::ObjectHeader>(addr)
+            .write(addr + Boxed::<T, W>::DATA, value);
+        Pointer::new(W::from_address(addr))
+    }
+
+    fn array_header<T>(&mut self, addr: Address, len: usize) -> &mut Self {
+        self.zeroed::<Array<T, W>>(addr)
+            .write(addr + offset_of!(Array<T, W>, size) as u64, len as u32)
+    }
+
+    fn zeroed<T>(&mut self, addr: Address) -> &mut Self {
+        self.write_bytes(addr, &vec![0; size_of::<T>()])
+    }
+
+    fn copy_to(&self, addr: Address, buf: &mut [u8]) -> bool {
+        // A read that wraps around the address space is never mapped.
+        let Some(end) = addr.value().checked_add(buf.len() as u64) else {
+            return false;
+        };
+        let mut addr = addr.value();
+        let mut buf = buf;
+        while addr < end {
+            let Some(page) = self.pages.get(&(addr / PAGE_SIZE)) else {
+                return false;
+            };
+            let offset = (addr % PAGE_SIZE) as usize;
+            let len = buf.len().min(PAGE_SIZE as usize - offset);
+            buf[..len].copy_from_slice(&page[offset..offset + len]);
+            buf = &mut buf[len..];
+            addr += len as u64;
+        }
+        true
+    }
+}
+
+impl<W: PointerWidth> MemReader for FakeHeap<W> {
+    fn read<T: CheckedBitPattern, A: Into<Address>>(&self, addr: A) -> Result<T, Error> {
+        let addr = addr.into();
+        crate::read_bits(addr, |buf| {
+            if self.copy_to(addr, buf) {
+                Ok(())
+            } else {
+                Err(Error::unreadable::<T>(addr))
+            }
+        })
+    }
+
+    fn read_bytes<A: Into<Address>>(&self, addr: A, buf: &mut [u8]) -> Result<(), Error> {
+        let addr = addr.into();
+        if self.copy_to(addr, buf) {
+            Ok(())
+        } else {
+            Err(Error::unreadable_bytes(addr, buf.len()))
+        }
+    }
+
+    fn read_into<T: CheckedBitPattern, A: Into<Address>>(
+        &self,
+        addr: A,
+        buf: &mut [T],
+    ) -> Result<(), Error> {
+        crate::read_into_chunked(self, addr, buf)
+    }
+}
+
+impl<W: PointerWidth> MemWriter for FakeHeap<W> {
+    fn write_bytes<A: Into<Address>>(&mut self, addr: A, bytes: &[u8]) -> Result<(), Error> {
+        self.try_write_bytes(addr.into(), bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Bit32;
+
+    #[test]
+    fn reads_what_was_written_across_pages() {
+        let mut heap = FakeHeap::new();
+        heap.write(0x1FFC_u64, 0x1122_3344_5566_7788_u64);
+        assert_eq!(heap.read::<u64, _>(0x1FFC_u64), Ok(0x1122_3344_5566_7788));
+        assert_eq!(heap.read::<u32, _>(0x2000_u64), Ok(0x1122_3344));
+        // The rest of a written page is zeroed.
+        assert_eq!(heap.read::<u64, _>(0x1000_u64), Ok(0));
+    }
+
+    #[test]
+    fn mem_writer_writes_what_is_read() {
+        let mut heap = FakeHeap::new();
+        MemWriter::write(&mut heap, 0x1FFE_u64, 0x1234_5678_u32).unwrap();
+        assert_eq!(heap.read::<u32, _>(0x1FFE_u64), Ok(0x1234_5678));
+        MemWriter::write_bytes(&mut heap, 0x1FFF_u64, &[0xAB, 0xCD]).unwrap();
+        let mut buf = [0_u8; 4];
+        heap.read_bytes(0x1FFE_u64, &mut buf).unwrap();
+        assert_eq!(buf, [0x78, 0xAB, 0xCD, 0x12]);
+    }
+
+    #[test]
+    fn unwritten_pages_are_unreadable() {
+        let mut heap = FakeHeap::new();
+        heap.write(0x1FFC_u64, 1_u32);
+        assert!(matches!(
+            heap.read::<u64, _>(0x1FFC_u64),
+            Err(Error::Unreadable { .. })
+        ));
+        assert!(heap.read::<u8, _>(0x5000_u64).is_err());
+        let mut buf = [0_u8; 8];
+        assert!(heap.read_bytes(0x1FFC_u64, &mut buf).is_err());
+    }
+
+    #[test]
+    fn accesses_that_wrap_around_fail() {
+        let mut heap = FakeHeap::new();
+        heap.write(u64::MAX - 15, u64::MAX).write(0_u64, 0_u64);
+        assert_eq!(heap.read::<u64, _>(u64::MAX - 15), Ok(u64::MAX));
+        // Like in a recording, an access may not include the last byte
+        // of the address space, as its end would not fit into an address.
+        assert_eq!(
+            heap.read::<u64, _>(u64::MAX - 7),
+            Err(Error::unreadable::<u64>(u64::MAX - 7))
+        );
+        assert_eq!(
+            MemWriter::write(&mut heap, u64::MAX - 3, 1_u64),
+            Err(Error::unwritable::<u64>(u64::MAX - 3))
+        );
+        // Neither end of the address space was touched.
+        assert_eq!(heap.read::<u64, _>(u64::MAX - 15), Ok(u64::MAX));
+        assert_eq!(heap.read::<u64, _>(0_u64), Ok(0));
+    }
+
+    #[test]
+    fn lays_out_collections() {
+        let mut heap = FakeHeap::new();
+        let string = heap.string(0x1000_u64, "Hello, 世界");
+        assert_eq!(string.to_std_string(&heap).unwrap(), "Hello, 世界");
+
+        let array = heap.array(0x2000_u64, &[1_u16, 2, 3]);
+        assert_eq!(array.to_vec(&heap).unwrap(), [1, 2, 3]);
+
+        let list = heap.list(0x3000_u64, 0x3100_u64, &[4_i64, 5]);
+        assert_eq!(list.to_vec(&heap).unwrap(), [4, 5]);
+
+        let boxed = heap.boxed(0x4000_u64, 6.5_f32);
+        assert_eq!(boxed.unbox(&heap), Ok(6.5));
+    }
+
+    #[test]
+    fn lays_out_32_bit_objects() {
+        let mut heap = FakeHeap::<Bit32>::with_width();
+        let string = heap.string(0x1000_u64, "Level 1");
+        assert_eq!(string.to_std_string(&heap).unwrap(), "Level 1");
+
+        let list = heap.list(0x2000_u64, 0x2100_u64, &[string]);
+        let item = list.get(&heap, 0).unwrap();
+        assert_eq!(item.to_std_string(&heap).unwrap(), "Level 1");
+    }
+}
@@ -0,0 +1,211 @@
+//! Caching of decoded strings.
+//!
+//! Managed strings are immutable, so a string only needs to be decoded
+//! again when a different string object is referenced. A [`StringCache`]
+//! keeps the decoded strings by the address and the length of their
+//! object, so that a lookup only reads the header of the string.
+//!
+//! ```
+//! use csharp_mem::{fake::FakeHeap, intern::StringCache};
+//!
+//! let mut heap = FakeHeap::new();
+//! let scene = heap.string(0x1000, "Level 1");
+//!
+//! let mut strings = StringCache::new();
+//! assert_eq!(strings.get(&heap, scene).unwrap(), "Level 1");
+//!
+//! // The contents of the string are only read once.
+//! assert_eq!(strings.to_std_string(&heap, scene).unwrap(), "Level 1");
+//! assert_eq!(strings.len(), 1);
+//! ```
+
+use alloc::{
+    collections::{btree_map::Entry, BTreeMap},
+    string::String,
+};
+
+use asr::{arrayvec::ArrayString, Address};
+
+use crate::{CSString, Error, MemReader, Pointer, PointerWidth};
+
+/// A cache of decoded strings, keyed by the address and the length
+/// of their string object.
+///
+/// The object of a string that is no longer used can be collected and its
+/// memory can be reused for a new string. If that string has the same
+/// length, the cache keeps returning the old one, until it is evicted.
+/// The cache holds up to [`capacity`](Self::capacity) strings and evicts
+/// the least recently used one to make room for a new one. Call
+/// [`clear`](Self::clear) when the cached strings are likely not needed
+/// anymore, e.g. after a scene change.
+#[derive(Clone, Debug)]
+pub struct StringCache {
+    /// The strings and when they were last used.
+    strings: BTreeMap<(u64, u32), (String, u64)>,
+    capacity: usize,
+    /// The number of lookups so far, which orders the uses of the strings.
+    uses: u64,
+}
+
+impl StringCache {
+    /// The number of strings that a cache holds by default.
+    pub const DEFAULT_CAPACITY: usize = 1024;
+
+    /// Create a new empty cache that holds up to
+    /// [`DEFAULT_CAPACITY`](Self::DEFAULT_CAPACITY) strings.
+    pub const fn new() -> Self {
+        Self::with_capacity(Self::DEFAULT_CAPACITY)
+    }
+
+    /// Create a new empty cache that holds up to `capacity` strings.
+    pub const fn with_capacity(capacity: usize) -> Self {
+        Self {
+            strings: BTreeMap::new(),
+            capacity,
+            uses: 0,
+        }
+    }
+
+    /// Returns the number of strings that the cache holds at most.
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the decoded string, which is only read if it is not cached.
+    pub fn get<R: MemReader, W: PointerWidth>(
+        &mut self,
+        reader: &R,
+        string: Pointer<CSString<W>, W>,
+    ) -> Result<&str, Error> {
+        let key = (Address::from(string).value(), string.read(reader)?.size());
+        self.uses += 1;
+        if self.strings.len() >= self.capacity.max(1) && !self.strings.contains_key(&key) {
+            self.evict();
+        }
+        let (string, used) = match self.strings.entry(key) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert((string.to_std_string(reader)?, 0)),
+        };
+        *used = self.uses;
+        Ok(string)
+    }
+
+    /// Like [`Pointer::<CSString>::to_string`], but only reads the string
+    /// if it is not cached.
+    ///
+    /// A string that does not fit into `CAP` bytes is truncated,
+    /// use [`to_string_checked`](Self::to_string_checked) to detect that.
+    pub fn to_string<R: MemReader, W: PointerWidth, const CAP: usize>(
+        &mut self,
+        reader: &R,
+        string: Pointer<CSString<W>, W>,
+    ) -> Result<ArrayString<CAP>, Error> {
+        let mut s = ArrayString::new();
+        for c in self.get(reader, string)?.chars() {
+            if s.try_push(c).is_err() {
+                break;
+            }
+        }
+        Ok(s)
+    }
+
+    /// Like [`Pointer::<CSString>::to_string_checked`], but only reads
+    /// the string if it is not cached.
+    pub fn to_string_checked<R: MemReader, W: PointerWidth, const CAP: usize>(
+        &mut self,
+        reader: &R,
+        string: Pointer<CSString<W>, W>,
+    ) -> Result<ArrayString<CAP>, Error> {
+        ArrayString::from(self.get(reader, string)?).map_err(|_| Error::Truncated {
+            address: string.into(),
+            capacity: CAP,
+        })
+    }
+
+    /// Like [`Pointer::<CSString>::to_std_string`], but only reads the
+    /// string if it is not cached.
+    pub fn to_std_string<R: MemReader, W: PointerWidth>(
+        &mut self,
+        reader: &R,
+        string: Pointer<CSString<W>, W>,
+    ) -> Result<String, Error> {
+        self.get(reader, string).map(String::from)
+    }
+
+    /// Returns the number of cached strings.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Returns `true` if no strings are cached.
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Drop all cached strings.
+    pub fn clear(&mut self) {
+        self.strings.clear();
+    }
+
+    /// Drop the least recently used string.
+    fn evict(&mut self) {
+        let oldest = self
+            .strings
+            .iter()
+            .min_by_key(|(_, (_, used))| *used)
+            .map(|(&key, _)| key);
+        if let Some(key) = oldest {
+            self.strings.remove(&key);
+        }
+    }
+}
+
+impl Default for StringCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake::FakeHeap;
+
+    #[test]
+    fn evicts_the_least_recently_used_string() {
+        let mut heap = FakeHeap::new();
+        let a = heap.string(0x1000_u64, "a");
+        let b = heap.string(0x2000_u64, "b");
+        let c = heap.string(0x3000_u64, "c");
+
+        let mut strings = StringCache::with_capacity(2);
+        assert_eq!(strings.get(&heap, a).unwrap(), "a");
+        assert_eq!(strings.get(&heap, b).unwrap(), "b");
+        assert_eq!(strings.get(&heap, a).unwrap(), "a");
+        assert_eq!(strings.get(&heap, c).unwrap(), "c");
+        assert_eq!(strings.len(), 2);
+
+        // "b" was evicted, so a new string at its address is read again,
+        // while "c" is still cached.
+        heap.string(0x2000_u64, "x");
+        heap.string(0x3000_u64, "y");
+        assert_eq!(strings.get(&heap, b).unwrap(), "x");
+        assert_eq!(strings.get(&heap, c).unwrap(), "c");
+    }
+
+    #[test]
+    fn to_string_checked_does_not_truncate() {
+        let mut heap = FakeHeap::new();
+        let s = heap.string(0x1000_u64, "abcdef");
+
+        let mut strings = StringCache::new();
+        let truncated = strings.to_string::<_, _, 4>(&heap, s).unwrap();
+        assert_eq!(truncated.as_str(), "abcd");
+        assert!(matches!(
+            strings.to_string_checked::<_, _, 4>(&heap, s),
+            Err(Error::Truncated { capacity: 4, .. })
+        ));
+        let full = strings.to_string_checked::<_, _, 6>(&heap, s).unwrap();
+        assert_eq!(full.as_str(), "abcdef");
+    }
+}
@@ -2,15 +2,45 @@
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
 use core::{
     fmt,
+    iter::FusedIterator,
     marker::PhantomData,
-    mem::{size_of, MaybeUninit},
+    mem::{align_of, size_of, size_of_val, MaybeUninit},
 };
 
-use asr::{arrayvec::ArrayString, Address, Address64, Process};
-use bytemuck::{AnyBitPattern, CheckedBitPattern};
+use asr::{arrayvec::ArrayString, Address, Address32, Address64, Process};
+use bytemuck::{AnyBitPattern, CheckedBitPattern, NoUninit, Pod};
+
+pub use class_id::{Ancestors, Backend, BackendReader, ClassId, WithBackend};
+pub use deep_pointer::DeepPointer;
+pub use error::Error;
+pub use limit::LimitedReader;
+
+#[doc(hidden)]
+pub use bytemuck as __bytemuck;
+
+#[cfg(feature = "alloc")]
+pub mod cache;
+mod class_id;
+mod deep_pointer;
+#[cfg(feature = "alloc")]
+pub mod dump;
+mod error;
+#[cfg(feature = "alloc")]
+pub mod fake;
+#[cfg(feature = "alloc")]
+pub mod intern;
+mod limit;
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub mod linux;
+#[cfg(feature = "alloc")]
+pub mod record;
+#[cfg(feature = "alloc")]
+pub mod stats;
 
 #[cfg(feature = "il2cpp")]
 pub use il2cpp::*;
@@ -21,15 +51,202 @@ pub use mono::*;
 /// Trait for things that can read data from memory.
 pub trait MemReader: Sized {
     /// Reads a value from memory.
-    fn read<T: CheckedBitPattern, A: Into<Address>>(&self, addr: A) -> Option<T>;
+    fn read<T: CheckedBitPattern, A: Into<Address>>(&self, addr: A) -> Result<T, Error>;
+
+    /// Reads raw bytes from memory into the buffer.
+    ///
+    /// The default implementation reads one byte at a time, readers
+    /// that can do better should override it.
+    fn read_bytes<A: Into<Address>>(&self, addr: A, buf: &mut [u8]) -> Result<(), Error> {
+        let addr = addr.into();
+        let len = buf.len();
+        for (offset, byte) in buf.iter_mut().enumerate() {
+            *byte = self
+                .read(addr + offset as u64)
+                .map_err(|_| Error::unreadable_bytes(addr, len))?;
+        }
+        Ok(())
+    }
+
+    /// Reads consecutive values from memory into the buffer.
+    ///
+    /// The default implementation reads one value at a time, readers
+    /// that can do better should override it, e.g. with [`read_into_chunked`].
+    fn read_into<T: CheckedBitPattern, A: Into<Address>>(
+        &self,
+        addr: A,
+        buf: &mut [T],
+    ) -> Result<(), Error> {
+        let addr = addr.into();
+        for (index, item) in buf.iter_mut().enumerate() {
+            *item = self.read(addr + (index * size_of::<T>()) as u64)?;
+        }
+        Ok(())
+    }
+
+    /// The maximum number of elements of an array, a list, a map or a
+    /// string that are iterated, see [`DEFAULT_MAX_LEN`].
+    ///
+    /// A pointer that has gone stale can point to a collection whose
+    /// length reads as billions, so iterating a collection that is longer
+    /// than this fails instead. Wrap a reader in a [`LimitedReader`] to
+    /// change the maximum, or use the `_with_max_len` variants of the
+    /// iterating methods to change it for a single call.
+    fn max_len(&self) -> usize {
+        DEFAULT_MAX_LEN
+    }
+}
+
+/// The default for [`MemReader::max_len`].
+pub const DEFAULT_MAX_LEN: usize = 1 << 24;
+
+/// Trait for things that can write data to memory.
+///
+/// There is no implementation for [`Process`], because the auto splitting
+/// runtime only allows reading the memory of the game.
+pub trait MemWriter: MemReader {
+    /// Writes raw bytes from the buffer to memory.
+    fn write_bytes<A: Into<Address>>(&mut self, addr: A, bytes: &[u8]) -> Result<(), Error>;
+
+    /// Writes a value to memory.
+    fn write<T: NoUninit, A: Into<Address>>(&mut self, addr: A, value: T) -> Result<(), Error> {
+        let addr = addr.into();
+        self.write_bytes(addr, bytemuck::bytes_of(&value))
+            .map_err(|_| Error::unwritable::<T>(addr))
+    }
+}
+
+/// Reads consecutive values from memory into the buffer by reading their
+/// bytes with [`MemReader::read_bytes`].
+///
+/// The bytes are read in chunks that fit into a bounded buffer on the
+/// stack, so that large arrays only need a few calls to the reader.
+/// This is meant to be used by [`MemReader`] implementations whose
+/// [`MemReader::read_bytes`] is cheaper than many calls to [`MemReader::read`].
+pub fn read_into_chunked<R: MemReader, T: CheckedBitPattern>(
+    reader: &R,
+    addr: impl Into<Address>,
+    buf: &mut [T],
+) -> Result<(), Error> {
+    const CHUNK: usize = 0x1000;
+
+    let addr = addr.into();
+    let size = size_of::<T>();
+    if size == 0 || size > CHUNK {
+        for (index, item) in buf.iter_mut().enumerate() {
+            let addr = addr + (index * size) as u64;
+            *item = read_bits(addr, |bytes| reader.read_bytes(addr, bytes))?;
+        }
+        return Ok(());
+    }
+
+    let mut bytes = [0_u8; CHUNK];
+    let per_chunk = CHUNK / size;
+    for (index, items) in buf.chunks_mut(per_chunk).enumerate() {
+        let start = addr + (index * per_chunk * size) as u64;
+        let bytes = &mut bytes[..size_of_val(items)];
+        reader.read_bytes(start, bytes)?;
+        for (offset, (item, bytes)) in items.iter_mut().zip(bytes.chunks_exact(size)).enumerate() {
+            *item = bytemuck::checked::try_pod_read_unaligned(bytes)
+                .map_err(|_| Error::invalid_bit_pattern::<T>(start + (offset * size) as u64))?;
+        }
+    }
+    Ok(())
+}
+
+impl<R: MemReader> MemReader for &R {
+    fn read<T: CheckedBitPattern, A: Into<Address>>(&self, addr: A) -> Result<T, Error> {
+        R::read(self, addr)
+    }
+
+    fn read_bytes<A: Into<Address>>(&self, addr: A, buf: &mut [u8]) -> Result<(), Error> {
+        R::read_bytes(self, addr, buf)
+    }
+
+    fn read_into<T: CheckedBitPattern, A: Into<Address>>(
+        &self,
+        addr: A,
+        buf: &mut [T],
+    ) -> Result<(), Error> {
+        R::read_into(self, addr, buf)
+    }
+
+    fn max_len(&self) -> usize {
+        R::max_len(self)
+    }
 }
 
 impl MemReader for Process {
-    fn read<T: CheckedBitPattern, A: Into<Address>>(&self, addr: A) -> Option<T> {
-        self.read(addr).ok()
+    fn read<T: CheckedBitPattern, A: Into<Address>>(&self, addr: A) -> Result<T, Error> {
+        let addr = addr.into();
+        // Read the raw bits first, so that an unmapped page can be told
+        // apart from memory that does not contain a valid `T`.
+        let bits =
+            Process::read::<T::Bits>(self, addr).map_err(|_| Error::unreadable::<T>(addr))?;
+        check_bit_pattern(addr, bits)
+    }
+
+    fn read_bytes<A: Into<Address>>(&self, addr: A, buf: &mut [u8]) -> Result<(), Error> {
+        let addr = addr.into();
+        self.read_into_buf(addr, buf)
+            .map_err(|_| Error::unreadable_bytes(addr, buf.len()))
+    }
+
+    fn read_into<T: CheckedBitPattern, A: Into<Address>>(
+        &self,
+        addr: A,
+        buf: &mut [T],
+    ) -> Result<(), Error> {
+        read_into_chunked(self, addr, buf)
+    }
+}
+
+/// Checks the length of the collection at `addr` against the maximum length.
+fn check_len(addr: Address, len: u32, max_len: usize) -> Result<(), Error> {
+    if len as usize > max_len {
+        Err(Error::TooLong {
+            address: addr,
+            len: len as usize,
+            max_len,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns the offset in bytes of the `T` at `index`.
+fn byte_offset<T>(index: usize) -> u64 {
+    (index as u64).saturating_mul(size_of::<T>() as u64)
+}
+
+/// Converts the raw bits of a `T` that have been read from `addr` into a `T`.
+fn check_bit_pattern<T: CheckedBitPattern>(addr: Address, bits: T::Bits) -> Result<T, Error> {
+    if T::is_valid_bit_pattern(&bits) {
+        // SAFETY: `T::Bits` has the same layout as `T` and we just
+        // checked that the bits are a valid `T`.
+        Ok(unsafe { ::core::mem::transmute_copy(&bits) })
+    } else {
+        Err(Error::invalid_bit_pattern::<T>(addr))
     }
 }
 
+/// Reads a `T` from `addr` by letting `fill` copy its raw bytes.
+fn read_bits<T: CheckedBitPattern>(
+    addr: Address,
+    fill: impl FnOnce(&mut [u8]) -> Result<(), Error>,
+) -> Result<T, Error> {
+    // A zeroed `MaybeUninit` has all of its bytes initialized, including
+    // the ones that would be padding in `T::Bits`.
+    let mut bits = MaybeUninit::<T::Bits>::zeroed();
+    // SAFETY: All bytes are initialized and any bit pattern is valid for `u8`.
+    let bytes = unsafe {
+        ::core::slice::from_raw_parts_mut(bits.as_mut_ptr().cast::<u8>(), size_of::<T::Bits>())
+    };
+    fill(bytes)?;
+    // SAFETY: `T::Bits` is `AnyBitPattern`, so any initialized bytes are valid.
+    check_bit_pattern(addr, unsafe { bits.assume_init() })
+}
+
 #[cfg(feature = "il2cpp")]
 mod il2cpp {
     use asr::{
@@ -38,13 +255,34 @@ mod il2cpp {
     };
     use bytemuck::CheckedBitPattern;
 
+    use crate::{Backend, BackendReader, Error, MemReader};
+
     pub use csharp_mem_derive::Il2cppClass as Class;
 
-    impl super::MemReader for Game<'_> {
-        fn read<T: CheckedBitPattern, A: Into<Address>>(&self, addr: A) -> Option<T> {
-            self.process().read(addr).ok()
+    impl MemReader for Game<'_> {
+        fn read<T: CheckedBitPattern, A: Into<Address>>(&self, addr: A) -> Result<T, Error> {
+            MemReader::read(self.process(), addr)
+        }
+
+        fn read_bytes<A: Into<Address>>(&self, addr: A, buf: &mut [u8]) -> Result<(), Error> {
+            MemReader::read_bytes(self.process(), addr, buf)
+        }
+
+        fn read_into<T: CheckedBitPattern, A: Into<Address>>(
+            &self,
+            addr: A,
+            buf: &mut [T],
+        ) -> Result<(), Error> {
+            MemReader::read_into(self.process(), addr, buf)
         }
     }
+
+    impl BackendReader for Game<'_> {
+        fn backend(&self) -> Backend {
+            Backend::Il2cpp
+        }
+    }
+
     /// Represents a Unity game that is using the IL2CPP backend.
     pub struct Game<'a> {
         process: &'a Process,
@@ -87,13 +325,34 @@ mod mono {
     };
     use bytemuck::CheckedBitPattern;
 
+    use crate::{Backend, BackendReader, Error, MemReader};
+
     pub use csharp_mem_derive::MonoClass as Class;
 
-    impl super::MemReader for Game<'_> {
-        fn read<T: CheckedBitPattern, A: Into<Address>>(&self, addr: A) -> Option<T> {
-            self.process().read(addr).ok()
+    impl MemReader for Game<'_> {
+        fn read<T: CheckedBitPattern, A: Into<Address>>(&self, addr: A) -> Result<T, Error> {
+            MemReader::read(self.process(), addr)
+        }
+
+        fn read_bytes<A: Into<Address>>(&self, addr: A, buf: &mut [u8]) -> Result<(), Error> {
+            MemReader::read_bytes(self.process(), addr, buf)
+        }
+
+        fn read_into<T: CheckedBitPattern, A: Into<Address>>(
+            &self,
+            addr: A,
+            buf: &mut [T],
+        ) -> Result<(), Error> {
+            MemReader::read_into(self.process(), addr, buf)
+        }
+    }
+
+    impl BackendReader for Game<'_> {
+        fn backend(&self) -> Backend {
+            Backend::Mono
         }
     }
+
     /// Represents a Unity game that is using the Mono backend.
     pub struct Game<'a> {
         process: &'a Process,
@@ -128,284 +387,1524 @@ mod mono {
     }
 }
 
+/// A derived class whose instances can be read from an object,
+/// which allows it to be a variant of a derived enum.
+///
+/// It is implemented by the derive macros for classes with instance
+/// fields that are not bound to a singleton.
+#[doc(hidden)]
+#[cfg(any(feature = "mono", feature = "il2cpp"))]
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be read from an object",
+    label = "not a derived class with instance fields",
+    note = "only derived classes with instance fields and without a `singleton` field can be variants of a derived enum"
+)]
+pub trait __InstanceClass: Sized {
+    /// The binding that is generated for the class.
+    type Binding;
+
+    /// The name of the class in the image, without its namespace.
+    const CLASS_NAME: &'static str;
+
+    /// Create the binding, see the generated `bind`.
+    fn bind() -> Self::Binding;
+
+    /// Read the instance at the given address through the binding.
+    fn read(binding: &mut Self::Binding, game: &Game<'_>, instance: Address)
+        -> Result<Self, Error>;
+}
+
+/// The pointer width of the process that is read from.
+///
+/// The layout of pointers and of all managed objects depends on it.
+/// It is either [`Bit64`] or [`Bit32`].
+pub trait PointerWidth: sealed::Sealed + Copy + 'static {
+    /// The address type that has the size of a pointer.
+    type Address: Pod + Into<Address> + fmt::Debug;
+
+    /// The header of every managed object, consisting of the
+    /// vtable and the sync block pointers.
+    type ObjectHeader: AnyBitPattern;
+
+    /// Convert an address into an address of this width,
+    /// truncating it if necessary.
+    fn from_address(address: Address) -> Self::Address;
+}
+
+/// Pointers are 64 bits wide, this is the default.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Bit64;
+
+/// Pointers are 32 bits wide.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Bit32;
+
+impl PointerWidth for Bit64 {
+    type Address = Address64;
+    type ObjectHeader = [u32; 4];
+
+    fn from_address(address: Address) -> Self::Address {
+        Address64::new(address.value())
+    }
+}
+
+impl PointerWidth for Bit32 {
+    type Address = Address32;
+    type ObjectHeader = [u32; 2];
+
+    fn from_address(address: Address) -> Self::Address {
+        Address32::new(address.value() as u32)
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for super::Bit64 {}
+    impl Sealed for super::Bit32 {}
+}
+
 /// A pointer to a value in memory.
-/// This type has the same memory layout as an [`Address64`] and
-/// can be used in place of it, typically in classes derived when
-/// the `derive` feature is enabled and used.
+/// This type has the same memory layout as an [`Address64`] (or an
+/// [`Address32`] for [`Bit32`]) and can be used in place of it,
+/// typically in classes derived when the `derive` feature is enabled
+/// and used.
 /// Using this type instead of [`Address64`] can give a bit more
 /// type safety.
 #[repr(C)]
-pub struct Pointer<T> {
-    address: Address64,
-    _t: PhantomData<T>,
+pub struct Pointer<T, W: PointerWidth = Bit64> {
+    address: W::Address,
+    _t: PhantomData<(T, W)>,
 }
 
-impl<T: CheckedBitPattern> Pointer<T> {
+impl<T: CheckedBitPattern, W: PointerWidth> Pointer<T, W> {
     /// Read a value from memory by following this pointer.
-    pub fn read<R: MemReader>(self, reader: &R) -> Option<T> {
-        if self.address.is_null() {
-            None
+    pub fn read<R: MemReader>(self, reader: &R) -> Result<T, Error> {
+        if self.addr().is_null() {
+            Err(Error::null_pointer::<T>())
+        } else {
+            reader.read(self.addr())
+        }
+    }
+}
+
+impl<T: NoUninit, W: PointerWidth> Pointer<T, W> {
+    /// Write a value to memory by following this pointer.
+    pub fn write<M: MemWriter>(self, writer: &mut M, value: T) -> Result<(), Error> {
+        if self.addr().is_null() {
+            Err(Error::null_pointer::<T>())
         } else {
-            reader.read(self.address)
+            writer.write(self.addr(), value)
         }
     }
 }
 
-impl<T> Pointer<T> {
+impl<T, W: PointerWidth> Pointer<T, W> {
+    /// Create a new pointer to a `T` at the given address.
+    pub const fn new(address: W::Address) -> Self {
+        Self {
+            address,
+            _t: PhantomData,
+        }
+    }
+
     /// Return the address of this pointer.
-    pub const fn address(self) -> Address64 {
+    pub const fn address(self) -> W::Address {
         self.address
     }
 
-    const unsafe fn cast<U>(self) -> Pointer<U> {
-        Pointer {
-            address: self.address,
-            _t: PhantomData,
+    /// Return a pointer to a field of type `U` at `offset` bytes from
+    /// the start of the `T`, e.g. from [`core::mem::offset_of!`] or from
+    /// an offset that has been resolved through the class metadata.
+    /// This allows reading a single field without reading the whole `T`.
+    ///
+    /// A null pointer stays null.
+    pub fn field<U>(self, offset: u64) -> Pointer<U, W> {
+        if self.addr().is_null() {
+            Pointer::new(self.address)
+        } else {
+            Pointer::new(W::from_address(self.addr() + offset))
         }
     }
+
+    /// Return a pointer to the `T` that is `count` elements after this one,
+    /// that is `count * size_of::<T>()` bytes.
+    ///
+    /// Like `pointer::wrapping_add`, the address wraps around instead of
+    /// overflowing, so a count that is out of range only results in a
+    /// pointer that fails to be read. A null pointer stays null.
+    // Named after `pointer::add`, which counts in elements as well.
+    #[allow(clippy::should_implement_trait)]
+    pub fn add(self, count: usize) -> Self {
+        self.field((count as u64).wrapping_mul(size_of::<T>() as u64))
+    }
+
+    fn addr(self) -> Address {
+        self.address.into()
+    }
 }
 
-impl<T: CheckedBitPattern + 'static> Pointer<Array<T>> {
-    pub fn iter<R: MemReader>(self, reader: &R) -> Option<ArrayIter<'_, T, R>> {
-        let array = self.read(reader)?;
-        let start = self.address() + Array::<T>::DATA;
-        let end = start + (size_of::<T>() * array.size as usize) as u64;
+impl<T: 'static, W: PointerWidth> Pointer<Pointer<T, W>, W> {
+    /// Read the pointer that this pointer points to.
+    pub fn deref<R: MemReader>(self, reader: &R) -> Result<Pointer<T, W>, Error> {
+        self.read(reader)
+    }
+}
 
-        Some(ArrayIter {
-            pos: start,
-            end,
-            reader,
-            _t: PhantomData,
-        })
+impl<T: CheckedBitPattern + 'static, W: PointerWidth> Pointer<Array<T, W>, W> {
+    pub fn iter<R: MemReader>(self, reader: &R) -> Result<ArrayIter<'_, T, R>, Error> {
+        self.iter_with_max_len(reader, reader.max_len())
+    }
+
+    /// Like [`iter`](Self::iter), but fails if the array has more than
+    /// `max_len` elements instead of using [`MemReader::max_len`].
+    pub fn iter_with_max_len<R: MemReader>(
+        self,
+        reader: &R,
+        max_len: usize,
+    ) -> Result<ArrayIter<'_, T, R>, Error> {
+        let array = self.read(reader)?;
+        check_len(self.addr(), array.size, max_len)?;
+        let start = self.addr() + Array::<T, W>::DATA;
+        Ok(ArrayIter::new(reader, start, array.size as usize))
     }
 
-    pub fn get<R: MemReader>(self, reader: &R, index: usize) -> Option<T> {
+    pub fn get<R: MemReader>(self, reader: &R, index: usize) -> Result<T, Error> {
         let array = self.read(reader)?;
         if index >= array.size as usize {
-            return None;
+            return Err(Error::index_out_of_bounds(
+                self.addr(),
+                index,
+                array.size as usize,
+            ));
         }
-        let offset = self.address() + Array::<T>::DATA + (index * size_of::<T>()) as u64;
+        let offset = self.addr() + Array::<T, W>::DATA + (index * size_of::<T>()) as u64;
         reader.read(offset)
     }
 
+    /// Copy the elements of the array into `buf`.
+    ///
+    /// Returns the number of elements that have been copied, which is
+    /// the smaller of the length of the array and the length of `buf`.
+    pub fn read_slice<R: MemReader>(self, reader: &R, buf: &mut [T]) -> Result<usize, Error> {
+        let array = self.read(reader)?;
+        let len = buf.len().min(array.size as usize);
+        reader.read_into(self.addr() + Array::<T, W>::DATA, &mut buf[..len])?;
+        Ok(len)
+    }
+
+    /// Copy the elements of the array into a [`Vec`](alloc::vec::Vec).
+    ///
+    /// Fails if the array has more than [`MemReader::max_len`] elements.
+    #[cfg(feature = "alloc")]
+    pub fn to_vec<R: MemReader>(self, reader: &R) -> Result<::alloc::vec::Vec<T>, Error> {
+        self.to_vec_with_limit(reader, usize::MAX)
+    }
+
+    #[cfg(feature = "alloc")]
+    fn to_vec_with_limit<R: MemReader>(
+        self,
+        reader: &R,
+        limit: usize,
+    ) -> Result<::alloc::vec::Vec<T>, Error> {
+        let array = self.read(reader)?;
+        let len = limit.min(array.size as usize);
+        check_len(self.addr(), len as u32, reader.max_len())?;
+        let start = self.addr() + Array::<T, W>::DATA;
+
+        // Read the raw bits in one go and only then check that they are valid.
+        let mut bits = ::alloc::vec![<T::Bits as ::bytemuck::Zeroable>::zeroed(); len];
+        reader.read_into(start, &mut bits)?;
+        bits.into_iter()
+            .enumerate()
+            .map(|(index, bits)| check_bit_pattern(start + (index * size_of::<T>()) as u64, bits))
+            .collect()
+    }
+
     /// # Safety
     ///
     /// This function is essentialy a `transmute` and thus is unsafe.
     /// All the safety requirements of `transmute` apply here.
-    pub unsafe fn as_slice<R: MemReader>(self, reader: &R) -> Option<&[MaybeUninit<T>]> {
+    /// The pointer is only valid if the reader is reading from the
+    /// current process, which is almost never the case.
+    #[deprecated(note = "use `read_slice` or `to_vec` instead, which copy the elements")]
+    pub unsafe fn as_slice<R: MemReader>(self, reader: &R) -> Result<&[MaybeUninit<T>], Error> {
         let array = self.read(reader)?;
         let len = array.size as usize;
-        let data = (self.address() + Array::<T>::DATA).value() as *const MaybeUninit<T>;
+        let data = (self.addr() + Array::<T, W>::DATA).value() as *const MaybeUninit<T>;
 
-        Some(::core::slice::from_raw_parts(data, len))
+        Ok(::core::slice::from_raw_parts(data, len))
     }
 }
 
-impl Pointer<CSString> {
-    pub fn chars<R: MemReader>(self, reader: &R) -> Option<impl Iterator<Item = char> + '_> {
-        let string = self.read(reader)?;
-        let start = self.address() + CSString::DATA;
-        let end = start + u64::from((size_of::<u16>() / size_of::<u8>()) as u32 * string.size);
+impl<T: NoUninit, W: PointerWidth> Pointer<Array<T, W>, W> {
+    /// Write `value` to the element at `index` of the array.
+    pub fn set<M: MemWriter>(self, writer: &mut M, index: usize, value: T) -> Result<(), Error> {
+        let array = self.read(writer)?;
+        if index >= array.size as usize {
+            return Err(Error::index_out_of_bounds(
+                self.addr(),
+                index,
+                array.size as usize,
+            ));
+        }
+        let offset = self.addr() + Array::<T, W>::DATA + (index * size_of::<T>()) as u64;
+        writer.write(offset, value)
+    }
+}
 
-        let utf16 = ArrayIter {
-            pos: start,
-            end,
-            reader,
-            _t: PhantomData::<u16>,
-        };
-        Some(char::decode_utf16(utf16).map(|o| o.unwrap_or(char::REPLACEMENT_CHARACTER)))
+impl<W: PointerWidth> Pointer<Array<u16, W>, W> {
+    /// Iterate over the chars of a `char[]`, decoded as UTF-16 like
+    /// the chars of a [`CSString`].
+    pub fn chars<R: MemReader>(
+        self,
+        reader: &R,
+    ) -> Result<impl Iterator<Item = Result<char, Error>> + '_, Error> {
+        Ok(DecodeUtf16::new(self.iter(reader)?))
     }
 
-    pub fn to_string<R: MemReader, const CAP: usize>(self, reader: &R) -> Option<ArrayString<CAP>> {
-        let chars = self.chars(reader)?;
-        let mut s = ArrayString::new();
-        for c in chars {
-            match s.try_push(c) {
-                Ok(()) => {}
-                Err(_) => break,
-            }
-        }
-        Some(s)
+    /// Like [`chars`](Self::chars), but fails if the array has more than
+    /// `max_len` elements instead of using [`MemReader::max_len`].
+    pub fn chars_with_max_len<R: MemReader>(
+        self,
+        reader: &R,
+        max_len: usize,
+    ) -> Result<impl Iterator<Item = Result<char, Error>> + '_, Error> {
+        Ok(DecodeUtf16::new(self.iter_with_max_len(reader, max_len)?))
+    }
+
+    pub fn to_string<R: MemReader, const CAP: usize>(
+        self,
+        reader: &R,
+    ) -> Result<ArrayString<CAP>, Error> {
+        collect_truncated(self.chars(reader)?)
     }
 
     #[cfg(feature = "alloc")]
-    pub fn to_std_string<R: MemReader>(self, reader: &R) -> Option<::alloc::string::String> {
-        Some(self.chars(reader)?.collect())
+    pub fn to_std_string<R: MemReader>(self, reader: &R) -> Result<::alloc::string::String, Error> {
+        self.chars(reader)?.collect()
     }
 }
 
-impl<T: CheckedBitPattern + 'static> Pointer<List<T>> {
-    pub fn iter<R: MemReader>(self, reader: &R) -> Option<impl Iterator<Item = T> + '_> {
-        let list = self.read(reader)?;
-        Some(list.items.iter(reader)?.take(list.size as _))
+impl<W: PointerWidth> Pointer<Array<u8, W>, W> {
+    /// Iterate over the chars of a `byte[]` that contains text
+    /// with the given encoding.
+    pub fn chars<R: MemReader>(
+        self,
+        reader: &R,
+        encoding: Encoding,
+    ) -> Result<impl Iterator<Item = Result<char, Error>> + '_, Error> {
+        Ok(DecodeBytes::new(self.iter(reader)?, encoding))
     }
 
-    pub fn get<R: MemReader>(self, reader: &R, index: usize) -> Option<T> {
-        let list = self.read(reader)?;
-        list.items.get(reader, index)
+    /// Like [`chars`](Self::chars), but fails if the array has more than
+    /// `max_len` elements instead of using [`MemReader::max_len`].
+    pub fn chars_with_max_len<R: MemReader>(
+        self,
+        reader: &R,
+        encoding: Encoding,
+        max_len: usize,
+    ) -> Result<impl Iterator<Item = Result<char, Error>> + '_, Error> {
+        Ok(DecodeBytes::new(
+            self.iter_with_max_len(reader, max_len)?,
+            encoding,
+        ))
     }
 
-    /// # Safety
-    ///
-    /// This function is essentialy a `transmute` and thus is unsafe.
-    /// All the safety requirements of `transmute` apply here.
-    pub unsafe fn as_slice<R: MemReader>(self, reader: &R) -> Option<&[T]> {
-        let list = self.read(reader)?;
-        let inner = list.items.as_slice(reader)?;
-        Some(&*(inner as *const [MaybeUninit<T>] as *const [T]))
+    pub fn to_string<R: MemReader, const CAP: usize>(
+        self,
+        reader: &R,
+        encoding: Encoding,
+    ) -> Result<ArrayString<CAP>, Error> {
+        collect_truncated(self.chars(reader, encoding)?)
     }
-}
 
-impl<K: AnyBitPattern + 'static, V: AnyBitPattern + 'static> Pointer<Map<K, V>> {
-    pub fn iter<R: MemReader>(self, reader: &R) -> Option<impl Iterator<Item = (K, V)> + '_> {
-        let map = self.read(reader)?;
-        Some(
-            map.entries
-                .iter(reader)?
-                .filter(|o| o._hash != 0 || o._next != 0)
-                .take(map.size as _)
-                .map(|o| (o.key, o.value)),
-        )
+    #[cfg(feature = "alloc")]
+    pub fn to_std_string<R: MemReader>(
+        self,
+        reader: &R,
+        encoding: Encoding,
+    ) -> Result<::alloc::string::String, Error> {
+        self.chars(reader, encoding)?.collect()
     }
 }
 
-impl<T: AnyBitPattern + 'static> Pointer<Set<T>> {
-    pub fn iter<R: MemReader>(self, reader: &R) -> Option<impl Iterator<Item = T> + '_> {
-        Some(
-            // SAFETY: Set<T> is repr(transparent) and is the same as Map<T, ()>
-            unsafe { self.cast::<Map<T, ()>>() }
-                .iter(reader)?
-                .map(|o| o.0),
-        )
+impl<W: PointerWidth> Pointer<CSString<W>, W> {
+    pub fn chars<R: MemReader>(
+        self,
+        reader: &R,
+    ) -> Result<impl Iterator<Item = Result<char, Error>> + '_, Error> {
+        self.chars_with_max_len(reader, reader.max_len())
     }
-}
 
-impl<T> From<Pointer<T>> for Address {
-    fn from(ptr: Pointer<T>) -> Self {
-        ptr.address.into()
+    /// Like [`chars`](Self::chars), but fails if the string has more than
+    /// `max_len` UTF-16 code units instead of using [`MemReader::max_len`].
+    pub fn chars_with_max_len<R: MemReader>(
+        self,
+        reader: &R,
+        max_len: usize,
+    ) -> Result<impl Iterator<Item = Result<char, Error>> + '_, Error> {
+        let string = self.read(reader)?;
+        check_len(self.addr(), string.size, max_len)?;
+        let start = self.addr() + CSString::<W>::DATA;
+        let utf16 = ArrayIter::<u16, R>::new(reader, start, string.size as usize);
+        Ok(DecodeUtf16::new(utf16))
     }
-}
 
-impl<T> From<Pointer<T>> for Address64 {
-    fn from(ptr: Pointer<T>) -> Self {
-        ptr.address
+    pub fn to_string<R: MemReader, const CAP: usize>(
+        self,
+        reader: &R,
+    ) -> Result<ArrayString<CAP>, Error> {
+        collect_truncated(self.chars(reader)?)
     }
-}
 
-impl<T> fmt::Debug for Pointer<T> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Pointer")
-            .field("address", &self.address)
-            .field("type", &core::any::type_name::<T>())
-            .finish()
+    /// Like [`to_string`](Self::to_string), but fails if the string
+    /// does not fit into `CAP` bytes instead of truncating it.
+    pub fn to_string_checked<R: MemReader, const CAP: usize>(
+        self,
+        reader: &R,
+    ) -> Result<ArrayString<CAP>, Error> {
+        let mut s = ArrayString::new();
+        for c in self.chars(reader)? {
+            if s.try_push(c?).is_err() {
+                return Err(Error::Truncated {
+                    address: self.addr(),
+                    capacity: CAP,
+                });
+            }
+        }
+        Ok(s)
     }
-}
 
-// This is a manual implementation and not derived because the derive
-// implementation would add a `T: Copy` bound, which is not required.
-impl<T> ::core::marker::Copy for Pointer<T> {}
+    #[cfg(feature = "alloc")]
+    pub fn to_std_string<R: MemReader>(self, reader: &R) -> Result<::alloc::string::String, Error> {
+        self.chars(reader)?.collect()
+    }
 
-// This is a manual implementation and not derived because the derive
-// implementation would add a `T: Clone` bound, which is not required.
-impl<T> ::core::clone::Clone for Pointer<T> {
-    fn clone(&self) -> Self {
-        *self
+    /// Compare the string with `other` without copying it.
+    ///
+    /// The lengths are compared first, so strings of a different
+    /// length are told apart without reading their contents.
+    pub fn eq_str<R: MemReader>(self, reader: &R, other: &str) -> Result<bool, Error> {
+        self.matches(reader, other, true, |unit, expected| unit == expected)
     }
-}
 
-// This is a manual implementation and not derived because the derive
-// macro would add a `T: AnyBitPattern` bound, which is not required.
-//
-// SAFETY:
-// Similar to raw pointers, a pointer is valid for any bit pattern
-// Dereferencing the pointer is not, though.
-unsafe impl<T: 'static> ::bytemuck::AnyBitPattern for Pointer<T> {}
+    /// Check if the string starts with `prefix` without copying it.
+    pub fn starts_with<R: MemReader>(self, reader: &R, prefix: &str) -> Result<bool, Error> {
+        self.matches(reader, prefix, false, |unit, expected| unit == expected)
+    }
 
-// This is a manual implementation and not derived because the derive
-// macro would add a `T: Zeroable` bound, which is not required.
-//
-// SAFETY:
-// A zeroed pointer is the null pointer, and it is a valid pointer.
-// It must not be derreferenced, though.
-unsafe impl<T: 'static> ::bytemuck::Zeroable for Pointer<T> {}
+    /// Compare the string with `other` like [`eq_str`](Self::eq_str),
+    /// but ignore the case of ASCII letters.
+    pub fn eq_ignore_ascii_case<R: MemReader>(
+        self,
+        reader: &R,
+        other: &str,
+    ) -> Result<bool, Error> {
+        self.matches(reader, other, true, |unit, expected| {
+            ascii_lowercase(unit) == ascii_lowercase(expected)
+        })
+    }
 
-#[repr(C)]
-pub struct Array<T> {
-    _type_id: u64,
-    _header: u64,
-    _header2: u64,
-    size: u32,
-    _t: PhantomData<T>,
+    /// Hash the string without copying it.
+    ///
+    /// The hash is the same as the one of [`hash_utf16`] for an equal
+    /// string, so it can be matched against precomputed hashes. It is
+    /// 64 bit FNV-1a and not the `GetHashCode` of the runtime, which
+    /// differs between runtimes and is randomized on some of them.
+    pub fn hash_utf16<R: MemReader>(self, reader: &R) -> Result<u64, Error> {
+        let string = self.read(reader)?;
+        check_len(self.addr(), string.size, reader.max_len())?;
+        let start = self.addr() + CSString::<W>::DATA;
+        ArrayIter::<u16, R>::new(reader, start, string.size as usize)
+            .try_fold(FNV_OFFSET_BASIS, |hash, unit| Ok(fnv1a(hash, unit?)))
+    }
+
+    /// Compare the UTF-16 code units of the string with the ones of
+    /// `other`. Unless `exact` is set, the string may be longer.
+    fn matches<R: MemReader>(
+        self,
+        reader: &R,
+        other: &str,
+        exact: bool,
+        eq: impl Fn(u16, u16) -> bool,
+    ) -> Result<bool, Error> {
+        let string = self.read(reader)?;
+        let size = string.size as usize;
+        let len = other.encode_utf16().count();
+        if size < len || (exact && size != len) {
+            return Ok(false);
+        }
+
+        let start = self.addr() + CSString::<W>::DATA;
+        let units = ArrayIter::<u16, R>::new(reader, start, len);
+        for (unit, expected) in units.zip(other.encode_utf16()) {
+            if !eq(unit?, expected) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
 }
 
-impl<T> Array<T> {
-    const DATA: u64 = 0x20;
+impl<W: PointerWidth> Pointer<StringBuilder<W>, W> {
+    /// Returns the number of UTF-16 code units of the string.
+    pub fn len<R: MemReader>(self, reader: &R) -> Result<usize, Error> {
+        Ok(self.read(reader)?.size() as usize)
+    }
 
-    pub const fn size(&self) -> u32 {
-        self.size
+    /// Iterate over the chars of all chunks, from the first to the last one.
+    ///
+    /// Every chunk is found by following the chunks from the last one,
+    /// which is cheap for the few chunks of most string builders.
+    pub fn chars<R: MemReader>(
+        self,
+        reader: &R,
+    ) -> Result<impl Iterator<Item = Result<char, Error>> + '_, Error> {
+        self.chars_with_max_len(reader, reader.max_len())
+    }
+
+    /// Like [`chars`](Self::chars), but fails if the string has more than
+    /// `max_len` UTF-16 code units instead of using [`MemReader::max_len`].
+    pub fn chars_with_max_len<R: MemReader>(
+        self,
+        reader: &R,
+        max_len: usize,
+    ) -> Result<impl Iterator<Item = Result<char, Error>> + '_, Error> {
+        let builder = self.read(reader)?;
+        check_len(self.addr(), builder.size(), max_len)?;
+        Ok(DecodeUtf16::new(ChunkUnits {
+            reader,
+            last: builder,
+            last_addr: self.addr(),
+            pos: 0,
+            len: builder.size() as usize,
+            chunk: ArrayIter::new(reader, Address::NULL, 0),
+        }))
+    }
+
+    pub fn to_string<R: MemReader, const CAP: usize>(
+        self,
+        reader: &R,
+    ) -> Result<ArrayString<CAP>, Error> {
+        collect_truncated(self.chars(reader)?)
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn to_std_string<R: MemReader>(self, reader: &R) -> Result<::alloc::string::String, Error> {
+        self.chars(reader)?.collect()
     }
 }
 
-const _: () = {
-    assert!(size_of::<Array<()>>() == Array::<()>::DATA as usize);
-};
+/// Collects chars into an [`ArrayString`] until the next one does not fit.
+fn collect_truncated<const CAP: usize>(
+    chars: impl Iterator<Item = Result<char, Error>>,
+) -> Result<ArrayString<CAP>, Error> {
+    let mut s = ArrayString::new();
+    for c in chars {
+        if s.try_push(c?).is_err() {
+            break;
+        }
+    }
+    Ok(s)
+}
 
-pub struct ArrayIter<'a, T, R> {
-    pos: Address64,
-    end: Address64,
+/// An iterator over the UTF-16 code units of all chunks of a [`StringBuilder`].
+struct ChunkUnits<'a, R, W: PointerWidth> {
     reader: &'a R,
-    _t: PhantomData<T>,
+    last: StringBuilder<W>,
+    last_addr: Address,
+    /// The position of the next code unit in the whole string.
+    pos: usize,
+    len: usize,
+    /// The remaining code units of the current chunk.
+    chunk: ArrayIter<'a, u16, R>,
 }
 
-impl<'a, T: CheckedBitPattern, R: MemReader> Iterator for ArrayIter<'a, T, R> {
-    type Item = T;
+impl<'a, R: MemReader, W: PointerWidth> ChunkUnits<'a, R, W> {
+    /// Find the chunk that contains the current position.
+    fn seek(&self) -> Result<ArrayIter<'a, u16, R>, Error> {
+        let (mut builder, mut addr) = (self.last, self.last_addr);
+        let mut hops = 0;
+        while builder.chunk_offset as usize > self.pos {
+            let previous = builder.chunk_previous;
+            let chunk = previous.read(self.reader)?;
+            // The offsets can not increase along the chain and only empty
+            // chunks share their offset, so a chain with more chunks than
+            // code units is garbage and might never end.
+            hops += 1;
+            if chunk.chunk_offset > builder.chunk_offset || hops > self.len + 1 {
+                return Err(Error::invalid_bit_pattern::<StringBuilder<W>>(
+                    previous.addr(),
+                ));
+            }
+            (builder, addr) = (chunk, previous.addr());
+        }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.pos >= self.end {
-            return None;
+        let offset = self.pos - builder.chunk_offset as usize;
+        let chunk_length = builder.chunk_length as usize;
+        if offset >= chunk_length {
+            return Err(Error::invalid_bit_pattern::<StringBuilder<W>>(addr));
         }
+        let chars = builder.chunk_chars.read(self.reader)?;
+        if builder.chunk_length > chars.size {
+            return Err(Error::InvalidLength {
+                address: addr,
+                len: chunk_length,
+                capacity: chars.size as usize,
+            });
+        }
+        let start =
+            builder.chunk_chars.addr() + Array::<u16, W>::DATA + (offset * size_of::<u16>()) as u64;
+        Ok(ArrayIter::new(self.reader, start, chunk_length - offset))
+    }
+}
 
-        let item: T = self.reader.read(self.pos)?;
+impl<'a, R: MemReader, W: PointerWidth> Iterator for ChunkUnits<'a, R, W> {
+    type Item = Result<u16, Error>;
 
-        self.pos = self.pos + size_of::<T>() as u64;
-        Some(item)
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(unit) = self.chunk.next() {
+                if unit.is_ok() {
+                    self.pos += 1;
+                } else {
+                    self.pos = self.len;
+                }
+                return Some(unit);
+            }
+            if self.pos >= self.len {
+                return None;
+            }
+            match self.seek() {
+                Ok(chunk) => self.chunk = chunk,
+                Err(e) => {
+                    self.pos = self.len;
+                    return Some(Err(e));
+                }
+            }
+        }
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let remaining = self.end.value().saturating_sub(self.pos.value()) as usize;
-        (remaining, Some(remaining))
+        // The iterator stops early after a failed read.
+        (0, Some(self.len - self.pos))
     }
 }
 
-impl<T> fmt::Debug for Array<T> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Array")
-            .field("size", &self.size)
-            .field("type", &core::any::type_name::<T>())
-            .finish()
-    }
-}
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
 
-// This is a manual implementation and not derived because the derive
-// implementation would add a `T: Copy` bound, which is not required.
-impl<T> ::core::marker::Copy for Array<T> {}
+/// Adds a UTF-16 code unit to a 64 bit FNV-1a hash.
+const fn fnv1a(hash: u64, unit: u16) -> u64 {
+    let hash = (hash ^ (unit & 0xFF) as u64).wrapping_mul(0x0000_0100_0000_01b3);
+    (hash ^ (unit >> 8) as u64).wrapping_mul(0x0000_0100_0000_01b3)
+}
 
-// This is a manual implementation and not derived because the derive
-// implementation would add a `T: Clone` bound, which is not required.
-impl<T> ::core::clone::Clone for Array<T> {
-    fn clone(&self) -> Self {
-        *self
+const fn ascii_lowercase(unit: u16) -> u16 {
+    if unit < 0x80 {
+        (unit as u8).to_ascii_lowercase() as u16
+    } else {
+        unit
     }
 }
 
-// This is a manual implementation and not derived because the derive
+/// Hashes the UTF-16 encoding of a string with 64 bit FNV-1a,
+/// the same way that [`Pointer::<CSString>::hash_utf16`] does.
+pub fn hash_utf16(s: &str) -> u64 {
+    s.encode_utf16().fold(FNV_OFFSET_BASIS, fnv1a)
+}
+
+impl<T: CheckedBitPattern + 'static, W: PointerWidth> Pointer<List<T, W>, W> {
+    pub fn iter<R: MemReader>(self, reader: &R) -> Result<ArrayIter<'_, T, R>, Error> {
+        self.iter_with_max_len(reader, reader.max_len())
+    }
+
+    /// Like [`iter`](Self::iter), but fails if the list has more than
+    /// `max_len` elements instead of using [`MemReader::max_len`].
+    ///
+    /// This also fails if the list claims to have more elements than
+    /// its backing array can hold.
+    pub fn iter_with_max_len<R: MemReader>(
+        self,
+        reader: &R,
+        max_len: usize,
+    ) -> Result<ArrayIter<'_, T, R>, Error> {
+        let list = self.read(reader)?;
+        check_len(self.addr(), list.size, max_len)?;
+        let items = list.items.read(reader)?;
+        if list.size > items.size {
+            return Err(Error::InvalidLength {
+                address: self.addr(),
+                len: list.size as usize,
+                capacity: items.size as usize,
+            });
+        }
+        let start = list.items.addr() + Array::<T, W>::DATA;
+        Ok(ArrayIter::new(reader, start, list.size as usize))
+    }
+
+    pub fn get<R: MemReader>(self, reader: &R, index: usize) -> Result<T, Error> {
+        let list = self.read(reader)?;
+        if index >= list.size as usize {
+            return Err(Error::index_out_of_bounds(
+                self.addr(),
+                index,
+                list.size as usize,
+            ));
+        }
+        list.items.get(reader, index)
+    }
+
+    /// Copy the elements of the list into `buf`.
+    ///
+    /// Returns the number of elements that have been copied, which is
+    /// the smaller of the length of the list and the length of `buf`.
+    pub fn read_slice<R: MemReader>(self, reader: &R, buf: &mut [T]) -> Result<usize, Error> {
+        let list = self.read(reader)?;
+        let len = buf.len().min(list.size as usize);
+        list.items.read_slice(reader, &mut buf[..len])
+    }
+
+    /// Copy the elements of the list into a [`Vec`](alloc::vec::Vec).
+    ///
+    /// Fails if the list has more than [`MemReader::max_len`] elements.
+    #[cfg(feature = "alloc")]
+    pub fn to_vec<R: MemReader>(self, reader: &R) -> Result<::alloc::vec::Vec<T>, Error> {
+        let list = self.read(reader)?;
+        check_len(self.addr(), list.size, reader.max_len())?;
+        list.items.to_vec_with_limit(reader, list.size as usize)
+    }
+
+    /// # Safety
+    ///
+    /// This function is essentialy a `transmute` and thus is unsafe.
+    /// All the safety requirements of `transmute` apply here.
+    /// The pointer is only valid if the reader is reading from the
+    /// current process, which is almost never the case.
+    #[deprecated(note = "use `read_slice` or `to_vec` instead, which copy the elements")]
+    pub unsafe fn as_slice<R: MemReader>(self, reader: &R) -> Result<&[T], Error> {
+        let list = self.read(reader)?;
+        #[allow(deprecated)]
+        let inner = list.items.as_slice(reader)?;
+        Ok(&*(inner as *const [MaybeUninit<T>] as *const [T]))
+    }
+}
+
+impl<K: AnyBitPattern + 'static, V: AnyBitPattern + 'static, W: PointerWidth>
+    Pointer<Map<K, V, W>, W>
+{
+    /// Iterate over the entries of the map.
+    ///
+    /// The iterator skips the unused slots of the map, so unlike
+    /// the iterators of arrays and lists, skipping entries still
+    /// reads the skipped slots.
+    pub fn iter<R: MemReader>(
+        self,
+        reader: &R,
+    ) -> Result<impl DoubleEndedIterator<Item = Result<(K, V), Error>> + FusedIterator + '_, Error>
+    {
+        self.iter_with_max_len(reader, reader.max_len())
+    }
+
+    /// Like [`iter`](Self::iter), but fails if the map has more than
+    /// `max_len` entries instead of using [`MemReader::max_len`].
+    pub fn iter_with_max_len<R: MemReader>(
+        self,
+        reader: &R,
+        max_len: usize,
+    ) -> Result<impl DoubleEndedIterator<Item = Result<(K, V), Error>> + FusedIterator + '_, Error>
+    {
+        let map = self.read(reader)?;
+        // Removed entries are not counted separately, so every entry
+        // before the size of the map might be used.
+        MapIter::new(
+            reader,
+            self.addr(),
+            map.entries,
+            map.size,
+            map.size,
+            max_len,
+        )
+    }
+}
+
+impl<K: AnyBitPattern + HashCode + 'static, V: AnyBitPattern + 'static, W: PointerWidth>
+    Pointer<Map<K, V, W>, W>
+{
+    /// Look up the value of `key`.
+    ///
+    /// Like the dictionary itself, this only reads the entries in the
+    /// bucket of the hash code of the key, see [`HashCode`].
+    ///
+    /// This expects the `Dictionary` of the .NET Framework reference source,
+    /// which both Mono and IL2CPP use: every bucket holds the index of the
+    /// first entry of its chain, or `-1` if it is empty. The `Dictionary`
+    /// of .NET Core stores the index plus one and is not supported.
+    pub fn get<R: MemReader>(self, reader: &R, key: &K) -> Result<Option<V>, Error> {
+        let map = self.read(reader)?;
+        let entry = find_entry::<Map<K, V, W>, _, _, _, _>(
+            reader,
+            self.addr(),
+            map.buckets,
+            map.entries,
+            key,
+            0,
+        )?;
+        Ok(entry.map(|entry| entry.value))
+    }
+
+    /// Check if the map contains `key`, see [`get`](Self::get).
+    pub fn contains_key<R: MemReader>(self, reader: &R, key: &K) -> Result<bool, Error> {
+        Ok(self.get(reader, key)?.is_some())
+    }
+}
+
+/// Walk the chain of entries in the bucket of `key`, where every
+/// bucket holds the index of its first entry plus `first`.
+/// `C` is the type of the collection at `addr`.
+fn find_entry<C, K, V, W, R>(
+    reader: &R,
+    addr: Address,
+    buckets: Pointer<Array<i32, W>, W>,
+    entries: Pointer<Array<Entry<K, V>, W>, W>,
+    key: &K,
+    first: i32,
+) -> Result<Option<Entry<K, V>>, Error>
+where
+    K: AnyBitPattern + HashCode + 'static,
+    V: AnyBitPattern + 'static,
+    W: PointerWidth,
+    R: MemReader,
+{
+    if buckets.addr().is_null() {
+        // The buckets are only allocated when the first key is added.
+        return Ok(None);
+    }
+    let bucket_count = buckets.read(reader)?.size;
+    if bucket_count == 0 {
+        return Ok(None);
+    }
+
+    let hash = (key.hash_code() & i32::MAX) as u32;
+    let bucket = hash % bucket_count;
+    let mut index = buckets.get(reader, bucket as usize)?.wrapping_sub(first);
+    // Every entry is in at most one chain, so a longer chain is garbage
+    // and might never end.
+    let mut remaining = entries.read(reader)?.size;
+    while let Ok(next) = usize::try_from(index) {
+        if remaining == 0 {
+            return Err(Error::invalid_bit_pattern::<C>(addr));
+        }
+        remaining -= 1;
+
+        let entry = entries.get(reader, next)?;
+        if entry.hash == hash && entry.key == *key {
+            return Ok(Some(entry));
+        }
+        index = entry.next;
+    }
+    Ok(None)
+}
+
+impl<V: AnyBitPattern + 'static, W: PointerWidth> Pointer<Map<Pointer<CSString<W>, W>, V, W>, W> {
+    /// Look up the value of the string key that is equal to `key`.
+    ///
+    /// The hash code of a string depends on the hashing of the runtime
+    /// and is not implemented by [`HashCode`], so unlike [`get`](Self::get)
+    /// this compares `key` with every key of the map,
+    /// see [`Pointer::<CSString>::eq_str`].
+    pub fn get_by_str<R: MemReader>(self, reader: &R, key: &str) -> Result<Option<V>, Error> {
+        for entry in self.iter(reader)? {
+            let (candidate, value) = entry?;
+            // The key of a removed entry is cleared.
+            if candidate.addr().is_null() {
+                continue;
+            }
+            if candidate.eq_str(reader, key)? {
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<T: AnyBitPattern + 'static, W: PointerWidth> Pointer<Set<T, W>, W> {
+    /// Iterate over the items of the set.
+    pub fn iter<R: MemReader>(
+        self,
+        reader: &R,
+    ) -> Result<impl DoubleEndedIterator<Item = Result<T, Error>> + FusedIterator + '_, Error> {
+        self.iter_with_max_len(reader, reader.max_len())
+    }
+
+    /// Like [`iter`](Self::iter), but fails if the set has more than
+    /// `max_len` items instead of using [`MemReader::max_len`].
+    pub fn iter_with_max_len<R: MemReader>(
+        self,
+        reader: &R,
+        max_len: usize,
+    ) -> Result<impl DoubleEndedIterator<Item = Result<T, Error>> + FusedIterator + '_, Error> {
+        let set = self.read(reader)?;
+        // Removed items leave a gap in the slots, so the items that are
+        // still used can be anywhere before the last index.
+        let iter = MapIter::new(
+            reader,
+            self.addr(),
+            set.slots,
+            set.last_index,
+            set.count,
+            max_len,
+        )?;
+        Ok(iter.map(|o| o.map(|o| o.0)))
+    }
+}
+
+impl<T: AnyBitPattern + HashCode + 'static, W: PointerWidth> Pointer<Set<T, W>, W> {
+    /// Check if the set contains `item`, see [`Pointer::<Map>::get`].
+    ///
+    /// Unlike the `Dictionary`, the `HashSet` of the reference source
+    /// holds the index of the first entry plus one in every bucket,
+    /// so that `0` is an empty bucket.
+    pub fn contains<R: MemReader>(self, reader: &R, item: &T) -> Result<bool, Error> {
+        let set = self.read(reader)?;
+        let slot = find_entry::<Set<T, W>, _, _, _, _>(
+            reader,
+            self.addr(),
+            set.buckets,
+            set.slots,
+            item,
+            1,
+        )?;
+        Ok(slot.is_some())
+    }
+}
+
+impl<T, W: PointerWidth> From<Pointer<T, W>> for Address {
+    fn from(ptr: Pointer<T, W>) -> Self {
+        ptr.addr()
+    }
+}
+
+impl<T> From<Pointer<T, Bit64>> for Address64 {
+    fn from(ptr: Pointer<T, Bit64>) -> Self {
+        ptr.address
+    }
+}
+
+impl<T> From<Pointer<T, Bit32>> for Address32 {
+    fn from(ptr: Pointer<T, Bit32>) -> Self {
+        ptr.address
+    }
+}
+
+impl<T, W: PointerWidth> fmt::Debug for Pointer<T, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pointer")
+            .field("address", &self.address)
+            .field("type", &core::any::type_name::<T>())
+            .finish()
+    }
+}
+
+// This is a manual implementation and not derived because the derive
+// implementation would add a `T: Copy` bound, which is not required.
+impl<T, W: PointerWidth> ::core::marker::Copy for Pointer<T, W> {}
+
+// This is a manual implementation and not derived because the derive
+// implementation would add a `T: Clone` bound, which is not required.
+impl<T, W: PointerWidth> ::core::clone::Clone for Pointer<T, W> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+// This is a manual implementation and not derived because the derive
+// macro would add a `T: AnyBitPattern` bound, which is not required.
+//
+// SAFETY:
+// Similar to raw pointers, a pointer is valid for any bit pattern
+// Dereferencing the pointer is not, though.
+unsafe impl<T: 'static, W: PointerWidth> ::bytemuck::AnyBitPattern for Pointer<T, W> {}
+
+// This is a manual implementation and not derived because the derive
+// macro would add a `T: Zeroable` bound, which is not required.
+//
+// SAFETY:
+// A zeroed pointer is the null pointer, and it is a valid pointer.
+// It must not be derreferenced, though.
+unsafe impl<T: 'static, W: PointerWidth> ::bytemuck::Zeroable for Pointer<T, W> {}
+
+// This is a manual implementation and not derived because the derive
+// macro would add a `T: NoUninit` bound, which is not required.
+//
+// SAFETY:
+// A pointer consists of only its address, which is `Pod`.
+unsafe impl<T: 'static, W: PointerWidth> ::bytemuck::NoUninit for Pointer<T, W> {}
+
+/// A [`Pointer`] that is never null.
+///
+/// Reading a null address as a `NonNullPointer` fails with
+/// [`Error::InvalidBitPattern`], so using this type for a field of a
+/// derived class states that the field must not be null, while a
+/// [`Pointer`] field can be null. Reading a null field of a derived
+/// class fails with [`Error::NullPointer`] instead.
+/// All methods of [`Pointer`] are available through [`Deref`](core::ops::Deref).
+#[repr(transparent)]
+pub struct NonNullPointer<T, W: PointerWidth = Bit64> {
+    pointer: Pointer<T, W>,
+}
+
+impl<T, W: PointerWidth> NonNullPointer<T, W> {
+    /// Create a new pointer to a `T` at the given address,
+    /// or `None` if the address is null.
+    pub fn new(address: W::Address) -> Option<Self> {
+        Pointer::new(address).non_null()
+    }
+
+    /// Return the address of this pointer.
+    pub const fn address(self) -> W::Address {
+        self.pointer.address
+    }
+
+    /// Return this pointer as a [`Pointer`] that could be null.
+    pub const fn pointer(self) -> Pointer<T, W> {
+        self.pointer
+    }
+}
+
+impl<T: CheckedBitPattern, W: PointerWidth> NonNullPointer<T, W> {
+    /// Read a value from memory by following this pointer.
+    pub fn read<R: MemReader>(self, reader: &R) -> Result<T, Error> {
+        reader.read(self.pointer.addr())
+    }
+}
+
+impl<T: 'static, W: PointerWidth> NonNullPointer<T, W> {
+    /// Read the pointer at the given address, which is a field of a
+    /// derived class, and fail with [`Error::NullPointer`] if it is null.
+    #[doc(hidden)]
+    pub fn __read_field<R: MemReader>(reader: &R, address: Address) -> Result<Self, Error> {
+        reader
+            .read::<Pointer<T, W>, _>(address)?
+            .non_null()
+            .ok_or(Error::null_pointer_at::<T>(address))
+    }
+}
+
+impl<T, W: PointerWidth> Pointer<T, W> {
+    /// Return this pointer as a [`NonNullPointer`], or `None` if it is null.
+    pub fn non_null(self) -> Option<NonNullPointer<T, W>> {
+        (!self.addr().is_null()).then_some(NonNullPointer { pointer: self })
+    }
+}
+
+impl<T, W: PointerWidth> ::core::ops::Deref for NonNullPointer<T, W> {
+    type Target = Pointer<T, W>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.pointer
+    }
+}
+
+impl<T, W: PointerWidth> From<NonNullPointer<T, W>> for Pointer<T, W> {
+    fn from(ptr: NonNullPointer<T, W>) -> Self {
+        ptr.pointer
+    }
+}
+
+impl<T, W: PointerWidth> From<NonNullPointer<T, W>> for Address {
+    fn from(ptr: NonNullPointer<T, W>) -> Self {
+        ptr.pointer.addr()
+    }
+}
+
+impl<T, W: PointerWidth> fmt::Debug for NonNullPointer<T, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NonNullPointer")
+            .field("address", &self.pointer.address)
+            .field("type", &core::any::type_name::<T>())
+            .finish()
+    }
+}
+
+// This is a manual implementation and not derived because the derive
+// implementation would add a `T: Copy` bound, which is not required.
+impl<T, W: PointerWidth> ::core::marker::Copy for NonNullPointer<T, W> {}
+
+// This is a manual implementation and not derived because the derive
+// implementation would add a `T: Clone` bound, which is not required.
+impl<T, W: PointerWidth> ::core::clone::Clone for NonNullPointer<T, W> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+// This is a manual implementation and not derived because the derive
+// macro would add a `T: CheckedBitPattern` bound, which is not required.
+//
+// SAFETY:
+// The bits are a `Pointer` with the same layout, which is valid
+// for a `NonNullPointer` as long as it is not null.
+unsafe impl<T: 'static, W: PointerWidth> ::bytemuck::CheckedBitPattern for NonNullPointer<T, W> {
+    type Bits = Pointer<T, W>;
+
+    fn is_valid_bit_pattern(bits: &Self::Bits) -> bool {
+        !bits.addr().is_null()
+    }
+}
+
+// This is a manual implementation and not derived because the derive
+// macro would add a `T: NoUninit` bound, which is not required.
+//
+// SAFETY:
+// A pointer consists of only its address, which is `Pod`.
+unsafe impl<T: 'static, W: PointerWidth> ::bytemuck::NoUninit for NonNullPointer<T, W> {}
+
+#[repr(C)]
+pub struct Array<T, W: PointerWidth = Bit64> {
+    _header: W::ObjectHeader,
+    _bounds: W::Address,
+    size: u32,
+    _t: PhantomData<T>,
+}
+
+impl<T, W: PointerWidth> Array<T, W> {
+    const DATA: u64 = size_of::<Self>() as u64;
+
+    pub const fn size(&self) -> u32 {
+        self.size
+    }
+}
+
+const _: () = {
+    assert!(Array::<(), Bit64>::DATA == 0x20);
+    assert!(Array::<(), Bit32>::DATA == 0x10);
+};
+
+/// The number of elements that an [`ArrayIter`] reads at once.
+const ITER_CHUNK_BYTES: usize = 512;
+
+/// The buffer of an [`ArrayIter`], aligned for the bits of any element
+/// that is buffered.
+#[repr(C, align(16))]
+struct IterChunk([MaybeUninit<u8>; ITER_CHUNK_BYTES]);
+
+/// An iterator over the elements of an array.
+///
+/// The elements are read in chunks of up to 512 bytes, from either end of
+/// the array with [`MemReader::read_into`], so that readers see the type of
+/// the elements. If a chunk cannot be read, its elements are read one by one.
+/// Skipping elements with [`Iterator::nth`] or [`Iterator::skip`] does not
+/// read the skipped elements. Every element that cannot be read is returned
+/// as an error, so the iterator always returns exactly as many items as
+/// the array has elements.
+pub struct ArrayIter<'a, T: CheckedBitPattern, R> {
+    start: Address,
+    /// The index of the next element from the front.
+    front: usize,
+    /// The index after the next element from the back.
+    back: usize,
+    reader: &'a R,
+    buf: IterChunk,
+    /// The index of the element at the start of `buf`.
+    buf_start: usize,
+    buf_len: usize,
+    /// The elements of the last chunk that could not be read.
+    unbuffered: core::ops::Range<usize>,
+    _t: PhantomData<T>,
+}
+
+impl<'a, T: CheckedBitPattern, R> ArrayIter<'a, T, R> {
+    /// The number of elements that are read at once, which is 0 for
+    /// elements that are too large or too aligned to be buffered.
+    const CHUNK: usize = if align_of::<T::Bits>() > align_of::<IterChunk>() {
+        0
+    } else {
+        ITER_CHUNK_BYTES
+            / if size_of::<T>() == 0 {
+                1
+            } else {
+                size_of::<T>()
+            }
+    };
+
+    fn new(reader: &'a R, start: Address, len: usize) -> Self {
+        Self {
+            start,
+            front: 0,
+            back: len,
+            reader,
+            buf: IterChunk([MaybeUninit::new(0); ITER_CHUNK_BYTES]),
+            buf_start: 0,
+            buf_len: 0,
+            unbuffered: 0..0,
+            _t: PhantomData,
+        }
+    }
+
+    fn addr(&self, index: usize) -> Address {
+        self.start + (size_of::<T>() * index) as u64
+    }
+
+    /// The buffer as the bits of `CHUNK` elements.
+    fn chunk(&mut self) -> &mut [T::Bits] {
+        // SAFETY: The buffer is aligned for `T::Bits` and large enough for
+        // `CHUNK` of them. It starts out zeroed and is only ever written
+        // as `T::Bits`, which is `AnyBitPattern`, so all elements are valid.
+        unsafe {
+            ::core::slice::from_raw_parts_mut(
+                self.buf.0.as_mut_ptr().cast::<T::Bits>(),
+                Self::CHUNK,
+            )
+        }
+    }
+}
+
+impl<'a, T: CheckedBitPattern, R: MemReader> ArrayIter<'a, T, R> {
+    /// Read the element at `index`, filling the buffer with the chunk
+    /// `chunk_start..chunk_start + chunk_len` if it is not buffered yet.
+    fn read_at(&mut self, index: usize, chunk_start: usize, chunk_len: usize) -> Result<T, Error> {
+        let buffered = self.buf_start..self.buf_start + self.buf_len;
+        if !buffered.contains(&index) && !self.unbuffered.contains(&index) {
+            let addr = self.addr(chunk_start);
+            let reader = self.reader;
+            let chunk = &mut self.chunk()[..chunk_len];
+            if chunk_len > 0 && reader.read_into(addr, chunk).is_ok() {
+                self.buf_start = chunk_start;
+                self.buf_len = chunk_len;
+            } else {
+                // A single element of the chunk might not be readable,
+                // so read the chunk one element at a time instead.
+                self.buf_len = 0;
+                self.unbuffered = chunk_start..chunk_start + chunk_len.max(1);
+            }
+        }
+
+        if self.unbuffered.contains(&index) {
+            self.reader.read(self.addr(index))
+        } else {
+            let offset = index - self.buf_start;
+            let bits = self.chunk()[offset];
+            check_bit_pattern(self.addr(index), bits)
+        }
+    }
+}
+
+impl<'a, T: CheckedBitPattern, R: MemReader> Iterator for ArrayIter<'a, T, R> {
+    type Item = Result<T, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front >= self.back {
+            return None;
+        }
+        let index = self.front;
+        self.front += 1;
+        let len = (self.back - index).min(Self::CHUNK);
+        Some(self.read_at(index, index, len))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.front = self.front.saturating_add(n).min(self.back);
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.back.saturating_sub(self.front);
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, T: CheckedBitPattern, R: MemReader> DoubleEndedIterator for ArrayIter<'a, T, R> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front >= self.back {
+            return None;
+        }
+        self.back -= 1;
+        let index = self.back;
+        let start = (index + 1)
+            .saturating_sub(Self::CHUNK.max(1))
+            .max(self.front);
+        let len = (index + 1 - start).min(Self::CHUNK);
+        Some(self.read_at(index, start, len))
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        self.back = self.back.saturating_sub(n).max(self.front);
+        self.next_back()
+    }
+}
+
+impl<'a, T: CheckedBitPattern, R: MemReader> ExactSizeIterator for ArrayIter<'a, T, R> {}
+
+impl<'a, T: CheckedBitPattern, R: MemReader> FusedIterator for ArrayIter<'a, T, R> {}
+
+/// An iterator over the used entries of a [`Map`].
+struct MapIter<'a, K: AnyBitPattern, V: AnyBitPattern, R> {
+    entries: ArrayIter<'a, Entry<K, V>, R>,
+    /// The number of used entries that have not been returned yet.
+    remaining: usize,
+}
+
+impl<'a, K: AnyBitPattern + 'static, V: AnyBitPattern + 'static, R: MemReader>
+    MapIter<'a, K, V, R>
+{
+    /// Iterate over the first `len` entries of the collection at `addr`,
+    /// of which at most `used` are still used.
+    fn new<W: PointerWidth>(
+        reader: &'a R,
+        addr: Address,
+        entries: Pointer<Array<Entry<K, V>, W>, W>,
+        len: u32,
+        used: u32,
+        max_len: usize,
+    ) -> Result<Self, Error> {
+        check_len(addr, len, max_len)?;
+        // The entries array has spare capacity, only the entries
+        // before `len` have ever been used.
+        let capacity = entries.read(reader)?.size;
+        if len > capacity {
+            return Err(Error::InvalidLength {
+                address: addr,
+                len: len as usize,
+                capacity: capacity as usize,
+            });
+        }
+        let start = entries.addr() + Array::<Entry<K, V>, W>::DATA;
+        Ok(Self {
+            entries: ArrayIter::new(reader, start, len as usize),
+            remaining: used.min(len) as usize,
+        })
+    }
+}
+
+impl<'a, K: AnyBitPattern, V: AnyBitPattern, R: MemReader> MapIter<'a, K, V, R> {
+    fn map_entry(
+        &mut self,
+        entry: Option<Result<Entry<K, V>, Error>>,
+    ) -> Option<Result<(K, V), Error>> {
+        match entry {
+            Some(Ok(entry)) => {
+                self.remaining -= 1;
+                Some(Ok((entry.key, entry.value)))
+            }
+            Some(Err(e)) => {
+                self.remaining = 0;
+                Some(Err(e))
+            }
+            None => {
+                // The map has fewer used entries than its size claims.
+                self.remaining = 0;
+                None
+            }
+        }
+    }
+}
+
+impl<'a, K: AnyBitPattern, V: AnyBitPattern, R: MemReader> Iterator for MapIter<'a, K, V, R> {
+    type Item = Result<(K, V), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let entry = self.entries.find(Entry::is_used);
+        self.map_entry(entry)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // The map might have fewer used entries than its size claims.
+        (0, Some(self.remaining))
+    }
+}
+
+impl<'a, K: AnyBitPattern, V: AnyBitPattern, R: MemReader> DoubleEndedIterator
+    for MapIter<'a, K, V, R>
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let entry = self.entries.rfind(Entry::is_used);
+        self.map_entry(entry)
+    }
+}
+
+impl<'a, K: AnyBitPattern, V: AnyBitPattern, R: MemReader> FusedIterator for MapIter<'a, K, V, R> {}
+
+/// Decodes UTF-16 code units into chars, replacing invalid surrogates
+/// with [`char::REPLACEMENT_CHARACTER`] and passing read errors through.
+struct DecodeUtf16<I> {
+    units: I,
+    buf: Option<u16>,
+}
+
+impl<I> DecodeUtf16<I> {
+    const fn new(units: I) -> Self {
+        Self { units, buf: None }
+    }
+}
+
+impl<I: Iterator<Item = Result<u16, Error>>> Iterator for DecodeUtf16<I> {
+    type Item = Result<char, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let unit = match self.buf.take() {
+            Some(unit) => unit,
+            None => match self.units.next()? {
+                Ok(unit) => unit,
+                Err(e) => return Some(Err(e)),
+            },
+        };
+
+        if !(0xD800..=0xDBFF).contains(&unit) {
+            let c = char::from_u32(u32::from(unit)).unwrap_or(char::REPLACEMENT_CHARACTER);
+            return Some(Ok(c));
+        }
+
+        let low = match self.units.next() {
+            Some(Ok(low)) => low,
+            Some(Err(e)) => return Some(Err(e)),
+            None => return Some(Ok(char::REPLACEMENT_CHARACTER)),
+        };
+
+        if !(0xDC00..=0xDFFF).contains(&low) {
+            self.buf = Some(low);
+            return Some(Ok(char::REPLACEMENT_CHARACTER));
+        }
+
+        let c = 0x10000 + ((u32::from(unit) - 0xD800) << 10) + (u32::from(low) - 0xDC00);
+        Some(Ok(char::from_u32(c).unwrap_or(char::REPLACEMENT_CHARACTER)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (low, high) = self.units.size_hint();
+        let buffered = usize::from(self.buf.is_some());
+        (
+            (low + buffered).div_ceil(2),
+            high.and_then(|h| h.checked_add(buffered)),
+        )
+    }
+}
+
+/// The encoding of the text in a `byte[]`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// UTF-8, which includes ASCII.
+    Utf8,
+    /// ISO 8859-1, where every byte is the code point of its char.
+    Latin1,
+}
+
+/// Decodes bytes into chars with the given [`Encoding`], replacing
+/// invalid sequences with [`char::REPLACEMENT_CHARACTER`] and passing
+/// read errors through.
+struct DecodeBytes<I> {
+    bytes: I,
+    encoding: Encoding,
+    buf: Option<u8>,
+}
+
+impl<I> DecodeBytes<I> {
+    const fn new(bytes: I, encoding: Encoding) -> Self {
+        Self {
+            bytes,
+            encoding,
+            buf: None,
+        }
+    }
+}
+
+impl<I: Iterator<Item = Result<u8, Error>>> Iterator for DecodeBytes<I> {
+    type Item = Result<char, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let byte = match self.buf.take() {
+            Some(byte) => byte,
+            None => match self.bytes.next()? {
+                Ok(byte) => byte,
+                Err(e) => return Some(Err(e)),
+            },
+        };
+        if self.encoding == Encoding::Latin1 || byte < 0x80 {
+            return Some(Ok(char::from(byte)));
+        }
+
+        // The valid range of the second byte depends on the first byte,
+        // to rule out overlong encodings, surrogates and code points
+        // above U+10FFFF. Like `String::from_utf8_lossy`, every maximal
+        // prefix of a valid sequence is replaced by a single char.
+        let (continuation, second) = match byte {
+            0xC2..=0xDF => (1, 0x80..=0xBF),
+            0xE0 => (2, 0xA0..=0xBF),
+            0xE1..=0xEC | 0xEE..=0xEF => (2, 0x80..=0xBF),
+            0xED => (2, 0x80..=0x9F),
+            0xF0 => (3, 0x90..=0xBF),
+            0xF1..=0xF3 => (3, 0x80..=0xBF),
+            0xF4 => (3, 0x80..=0x8F),
+            _ => return Some(Ok(char::REPLACEMENT_CHARACTER)),
+        };
+
+        let mut c = u32::from(byte) & (0x7F >> (continuation + 1));
+        for index in 0..continuation {
+            let next = match self.bytes.next() {
+                Some(Ok(next)) => next,
+                Some(Err(e)) => return Some(Err(e)),
+                None => return Some(Ok(char::REPLACEMENT_CHARACTER)),
+            };
+            let valid = if index == 0 {
+                second.clone()
+            } else {
+                0x80..=0xBF
+            };
+            if !valid.contains(&next) {
+                self.buf = Some(next);
+                return Some(Ok(char::REPLACEMENT_CHARACTER));
+            }
+            c = (c << 6) | u32::from(next & 0x3F);
+        }
+        Some(Ok(char::from_u32(c).unwrap_or(char::REPLACEMENT_CHARACTER)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (low, high) = self.bytes.size_hint();
+        let buffered = usize::from(self.buf.is_some());
+        let low = match self.encoding {
+            Encoding::Utf8 => (low + buffered).div_ceil(4),
+            Encoding::Latin1 => low + buffered,
+        };
+        (low, high.and_then(|h| h.checked_add(buffered)))
+    }
+}
+
+impl<T, W: PointerWidth> fmt::Debug for Array<T, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Array")
+            .field("size", &self.size)
+            .field("type", &core::any::type_name::<T>())
+            .finish()
+    }
+}
+
+// This is a manual implementation and not derived because the derive
+// implementation would add a `T: Copy` bound, which is not required.
+impl<T, W: PointerWidth> ::core::marker::Copy for Array<T, W> {}
+
+// This is a manual implementation and not derived because the derive
+// implementation would add a `T: Clone` bound, which is not required.
+impl<T, W: PointerWidth> ::core::clone::Clone for Array<T, W> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+// This is a manual implementation and not derived because the derive
 // macro would add a `T: AnyBitPattern` bound, which is not required.
 //
 // SAFETY:
 // While technically not any bit pattern is allowed, we are ignoring
 // the C# object header internals, so for the purpose of this type
 // they can indeed be anything.
-unsafe impl<T: 'static> ::bytemuck::AnyBitPattern for Array<T> {}
+unsafe impl<T: 'static, W: PointerWidth> ::bytemuck::AnyBitPattern for Array<T, W> {}
 
 // This is a manual implementation and not derived because the derive
 // macro would add a `T: Zeroable` bound, which is not required.
@@ -414,30 +1913,30 @@ unsafe impl<T: 'static> ::bytemuck::AnyBitPattern for Array<T> {}
 // Similar to the logic for AnyBitPattern, we accept zeroed values
 // because we only care about the size field and that one is ok
 // to be zero.
-unsafe impl<T: 'static> ::bytemuck::Zeroable for Array<T> {}
+unsafe impl<T: 'static, W: PointerWidth> ::bytemuck::Zeroable for Array<T, W> {}
 
 #[repr(C)]
-pub struct CSString {
-    _type_id: [u32; 2],
-    _header: [u32; 2],
+pub struct CSString<W: PointerWidth = Bit64> {
+    _header: W::ObjectHeader,
     size: u32,
 }
 
-impl CSString {
-    const DATA: u64 = 0x14;
+impl<W: PointerWidth> CSString<W> {
+    const DATA: u64 = size_of::<Self>() as u64;
 }
 
 const _: () = {
-    assert!(size_of::<CSString>() == CSString::DATA as usize);
+    assert!(CSString::<Bit64>::DATA == 0x14);
+    assert!(CSString::<Bit32>::DATA == 0x0C);
 };
 
-impl CSString {
+impl<W: PointerWidth> CSString<W> {
     pub const fn size(&self) -> u32 {
         self.size
     }
 }
 
-impl fmt::Debug for CSString {
+impl<W: PointerWidth> fmt::Debug for CSString<W> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("CSString")
             .field("size", &self.size)
@@ -446,11 +1945,11 @@ impl fmt::Debug for CSString {
 }
 // This is a manual implementation and not derived because the derive
 // implementation would add a `T: Copy` bound, which is not required.
-impl ::core::marker::Copy for CSString {}
+impl<W: PointerWidth> ::core::marker::Copy for CSString<W> {}
 
 // This is a manual implementation and not derived because the derive
 // implementation would add a `T: Clone` bound, which is not required.
-impl ::core::clone::Clone for CSString {
+impl<W: PointerWidth> ::core::clone::Clone for CSString<W> {
     fn clone(&self) -> Self {
         *self
     }
@@ -458,27 +1957,71 @@ impl ::core::clone::Clone for CSString {
 
 // This is a manual implementation and not derived because the derive
 // macro would add a `T: AnyBitPattern` bound, which is not required.
-unsafe impl ::bytemuck::AnyBitPattern for CSString {}
+unsafe impl<W: PointerWidth> ::bytemuck::AnyBitPattern for CSString<W> {}
 
 // This is a manual implementation and not derived because the derive
 // macro would add a `T: Zeroable` bound, which is not required.
-unsafe impl ::bytemuck::Zeroable for CSString {}
+unsafe impl<W: PointerWidth> ::bytemuck::Zeroable for CSString<W> {}
+
+/// A `System.Text.StringBuilder`.
+///
+/// The characters of a string builder are stored in a linked list of
+/// chunks, starting with the last chunk. Every chunk is a string builder
+/// itself, whose characters continue at its offset into the whole string.
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct StringBuilder<W: PointerWidth = Bit64> {
+    _header: W::ObjectHeader,
+    chunk_chars: Pointer<Array<u16, W>, W>,
+    chunk_previous: Pointer<StringBuilder<W>, W>,
+    chunk_length: u32,
+    chunk_offset: u32,
+    _max_capacity: u32,
+}
+
+impl<W: PointerWidth> StringBuilder<W> {
+    /// Returns the number of UTF-16 code units of the whole string,
+    /// if this is the last chunk.
+    pub const fn size(&self) -> u32 {
+        self.chunk_offset.saturating_add(self.chunk_length)
+    }
+}
+
+impl<W: PointerWidth> fmt::Debug for StringBuilder<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StringBuilder")
+            .field("chunk_chars", &self.chunk_chars)
+            .field("chunk_previous", &self.chunk_previous)
+            .field("chunk_length", &self.chunk_length)
+            .field("chunk_offset", &self.chunk_offset)
+            .finish()
+    }
+}
+
+// This is a manual implementation and not derived because the derive
+// macro would require `W: AnyBitPattern`, which the pointer widths do
+// not implement, as they are never read from memory themselves.
+unsafe impl<W: PointerWidth> ::bytemuck::AnyBitPattern for StringBuilder<W> {}
+
+// This is a manual implementation and not derived because the derive
+// macro would require `W: Zeroable`, which the pointer widths do not
+// implement, as they are never read from memory themselves.
+unsafe impl<W: PointerWidth> ::bytemuck::Zeroable for StringBuilder<W> {}
 
 #[repr(C)]
-pub struct List<T> {
-    _type_id: u64,
-    _header: u64,
-    items: Pointer<Array<T>>,
+pub struct List<T, W: PointerWidth = Bit64> {
+    _header: W::ObjectHeader,
+    items: Pointer<Array<T, W>, W>,
     size: u32,
 }
 
-impl<T> List<T> {
+impl<T, W: PointerWidth> List<T, W> {
     pub const fn size(&self) -> u32 {
         self.size
     }
 }
 
-impl<T> fmt::Debug for List<T> {
+impl<T, W: PointerWidth> fmt::Debug for List<T, W> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("List")
             .field("items", &self.items)
@@ -489,11 +2032,11 @@ impl<T> fmt::Debug for List<T> {
 
 // This is a manual implementation and not derived because the derive
 // implementation would add a `T: Copy` bound, which is not required.
-impl<T> ::core::marker::Copy for List<T> {}
+impl<T, W: PointerWidth> ::core::marker::Copy for List<T, W> {}
 
 // This is a manual implementation and not derived because the derive
 // implementation would add a `T: Clone` bound, which is not required.
-impl<T> ::core::clone::Clone for List<T> {
+impl<T, W: PointerWidth> ::core::clone::Clone for List<T, W> {
     fn clone(&self) -> Self {
         *self
     }
@@ -501,22 +2044,21 @@ impl<T> ::core::clone::Clone for List<T> {
 
 // This is a manual implementation and not derived because the derive
 // macro would add a `T: AnyBitPattern` bound, which is not required.
-unsafe impl<T: 'static> ::bytemuck::AnyBitPattern for List<T> {}
+unsafe impl<T: 'static, W: PointerWidth> ::bytemuck::AnyBitPattern for List<T, W> {}
 
 // This is a manual implementation and not derived because the derive
 // macro would add a `T: Zeroable` bound, which is not required.
-unsafe impl<T: 'static> ::bytemuck::Zeroable for List<T> {}
+unsafe impl<T: 'static, W: PointerWidth> ::bytemuck::Zeroable for List<T, W> {}
 
 #[repr(C)]
-pub struct Map<K, V> {
-    _type_id: u64,
-    _header: u64,
-    _header_2: u64,
-    entries: Pointer<Array<Entry<K, V>>>,
+pub struct Map<K, V, W: PointerWidth = Bit64> {
+    _header: W::ObjectHeader,
+    buckets: Pointer<Array<i32, W>, W>,
+    entries: Pointer<Array<Entry<K, V>, W>, W>,
     size: u32,
 }
 
-impl<K, V> Map<K, V> {
+impl<K, V, W: PointerWidth> Map<K, V, W> {
     pub const fn size(&self) -> u32 {
         self.size
     }
@@ -525,13 +2067,96 @@ impl<K, V> Map<K, V> {
 #[derive(Copy, Clone, Debug, AnyBitPattern)]
 #[repr(C)]
 struct Entry<K, V> {
-    _hash: u32,
-    _next: u32,
+    hash: u32,
+    next: i32,
     key: K,
     value: V,
 }
 
-impl<K, V> fmt::Debug for Map<K, V> {
+impl<K, V> Entry<K, V> {
+    /// Errors are passed through, so that iteration stops at them.
+    ///
+    /// Only the entries that have been used at some point are iterated.
+    /// Removed entries keep their slot with a hash code of `-1`,
+    /// which no used entry has, see [`HashCode`].
+    fn is_used(entry: &Result<Self, Error>) -> bool {
+        entry
+            .as_ref()
+            .map_or(true, |entry| entry.hash <= i32::MAX as u32)
+    }
+}
+
+/// Types that can be the key of a [`Map`] that is looked up with
+/// [`Pointer::<Map>::get`], because their .NET hash code is known.
+///
+/// The implementations match the `GetHashCode` of the .NET type with the
+/// same layout, e.g. `int` for [`i32`] and `ushort` for [`u16`]. For enums,
+/// .NET uses the hash code of the underlying type, so an enum key can
+/// delegate to the implementation of its underlying type.
+///
+/// Like the reference source of the .NET Framework, which Mono and IL2CPP
+/// use, the entries store the hash code masked with `i32::MAX`, so that
+/// `-1` can mark a removed entry. .NET Core stores the hash code as is.
+pub trait HashCode: PartialEq {
+    /// Returns the same value as `GetHashCode` in .NET.
+    fn hash_code(&self) -> i32;
+}
+
+impl HashCode for bool {
+    fn hash_code(&self) -> i32 {
+        i32::from(*self)
+    }
+}
+
+impl HashCode for u8 {
+    fn hash_code(&self) -> i32 {
+        i32::from(*self)
+    }
+}
+
+impl HashCode for i8 {
+    fn hash_code(&self) -> i32 {
+        i32::from(*self) ^ (i32::from(*self) << 8)
+    }
+}
+
+impl HashCode for u16 {
+    fn hash_code(&self) -> i32 {
+        i32::from(*self)
+    }
+}
+
+impl HashCode for i16 {
+    fn hash_code(&self) -> i32 {
+        i32::from(*self as u16) | (i32::from(*self) << 16)
+    }
+}
+
+impl HashCode for u32 {
+    fn hash_code(&self) -> i32 {
+        *self as i32
+    }
+}
+
+impl HashCode for i32 {
+    fn hash_code(&self) -> i32 {
+        *self
+    }
+}
+
+impl HashCode for u64 {
+    fn hash_code(&self) -> i32 {
+        (*self as i32) ^ ((*self >> 32) as i32)
+    }
+}
+
+impl HashCode for i64 {
+    fn hash_code(&self) -> i32 {
+        (*self as i32) ^ ((*self >> 32) as i32)
+    }
+}
+
+impl<K, V, W: PointerWidth> fmt::Debug for Map<K, V, W> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Map")
             .field("entries", &self.entries)
@@ -543,12 +2168,12 @@ impl<K, V> fmt::Debug for Map<K, V> {
 // This is a manual implementation and not derived because the derive
 // implementation would add `K: Copy` and `V: Copy` bounds, which is
 // not required.
-impl<K, V> ::core::marker::Copy for Map<K, V> {}
+impl<K, V, W: PointerWidth> ::core::marker::Copy for Map<K, V, W> {}
 
 // This is a manual implementation and not derived because the derive
 // implementation would add `K: Clone` and `V: Clone` bounds, which is
 // not required.
-impl<K, V> ::core::clone::Clone for Map<K, V> {
+impl<K, V, W: PointerWidth> ::core::clone::Clone for Map<K, V, W> {
     fn clone(&self) -> Self {
         *self
     }
@@ -557,37 +2182,361 @@ impl<K, V> ::core::clone::Clone for Map<K, V> {
 // This is a manual implementation and not derived because the derive
 // macro would add `K: AnyBitPattern` and `V: AnyBitPattern` bounds,
 // which is not required.
-unsafe impl<K: 'static, V: 'static> ::bytemuck::AnyBitPattern for Map<K, V> {}
+unsafe impl<K: 'static, V: 'static, W: PointerWidth> ::bytemuck::AnyBitPattern for Map<K, V, W> {}
 
 // This is a manual implementation and not derived because the derive
 // macro would add `K: Zeroable` and `V: Zeroable` bounds, which is
 // not required.
-unsafe impl<K: 'static, V: 'static> ::bytemuck::Zeroable for Map<K, V> {}
+unsafe impl<K: 'static, V: 'static, W: PointerWidth> ::bytemuck::Zeroable for Map<K, V, W> {}
 
-#[repr(transparent)]
-pub struct Set<T> {
-    map: Map<T, ()>,
+/// A `HashSet<T>` of the .NET Framework reference source.
+///
+/// Unlike a `Dictionary`, the set keeps the index after its last used
+/// slot next to the number of items, because removing an item leaves
+/// a gap in the slots that is only reused by the next item that is added.
+#[repr(C)]
+pub struct Set<T, W: PointerWidth = Bit64> {
+    _header: W::ObjectHeader,
+    buckets: Pointer<Array<i32, W>, W>,
+    slots: Pointer<Array<Entry<T, ()>, W>, W>,
+    count: u32,
+    last_index: u32,
+}
+
+impl<T, W: PointerWidth> Set<T, W> {
+    pub const fn size(&self) -> u32 {
+        self.count
+    }
+}
+
+impl<T, W: PointerWidth> fmt::Debug for Set<T, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Set")
+            .field("slots", &self.slots)
+            .field("count", &self.count)
+            .field("last_index", &self.last_index)
+            .finish()
+    }
+}
+
+// This is a manual implementation and not derived because the derive
+// implementation would add a `T: Copy` bound, which is not required.
+impl<T, W: PointerWidth> ::core::marker::Copy for Set<T, W> {}
+
+// This is a manual implementation and not derived because the derive
+// implementation would add a `T: Clone` bound, which is not required.
+impl<T, W: PointerWidth> ::core::clone::Clone for Set<T, W> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+// This is a manual implementation and not derived because the derive
+// macro would add a `T: AnyBitPattern` bound, which is not required.
+unsafe impl<T: 'static, W: PointerWidth> ::bytemuck::AnyBitPattern for Set<T, W> {}
+
+// This is a manual implementation and not derived because the derive
+// macro would add a `T: Zeroable` bound, which is not required.
+unsafe impl<T: 'static, W: PointerWidth> ::bytemuck::Zeroable for Set<T, W> {}
+
+/// A boxed value type, e.g. a value that is stored in a field of type `object`.
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct Boxed<T, W: PointerWidth = Bit64> {
+    _header: W::ObjectHeader,
+    value: T,
+}
+
+impl<T, W: PointerWidth> Boxed<T, W> {
+    const DATA: u64 = size_of::<W::ObjectHeader>() as u64;
+}
+
+impl<T: Copy, W: PointerWidth> Boxed<T, W> {
+    pub const fn value(&self) -> T {
+        self.value
+    }
+}
+
+const _: () = {
+    assert!(Boxed::<u64, Bit64>::DATA == 0x10);
+    assert!(Boxed::<u64, Bit32>::DATA == 0x08);
+};
+
+impl<T: CheckedBitPattern, W: PointerWidth> Pointer<Boxed<T, W>, W> {
+    /// Read the value out of the box.
+    pub fn unbox<R: MemReader>(self, reader: &R) -> Result<T, Error> {
+        self.field::<T>(Boxed::<T, W>::DATA).read(reader)
+    }
+}
+
+impl<T: fmt::Debug, W: PointerWidth> fmt::Debug for Boxed<T, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Boxed").field("value", &self.value).finish()
+    }
+}
+
+// This is a manual implementation and not derived because the derive
+// macro would require `W: AnyBitPattern`, which the pointer widths do
+// not implement, as they are never read from memory themselves.
+//
+// SAFETY:
+// The object header can be anything for our purposes, same as for `Array`,
+// and the value is valid for any bit pattern.
+unsafe impl<T: AnyBitPattern, W: PointerWidth> ::bytemuck::AnyBitPattern for Boxed<T, W> {}
+
+// This is a manual implementation and not derived because the derive
+// macro would require `W: Zeroable`, which the pointer widths do not
+// implement, as they are never read from memory themselves.
+//
+// SAFETY:
+// Both the header and the value are zeroable.
+unsafe impl<T: AnyBitPattern, W: PointerWidth> ::bytemuck::Zeroable for Boxed<T, W> {}
+
+/// A `Nullable<T>`, also written as `T?`.
+///
+/// The value is aligned to the alignment of `T` after the `hasValue`
+/// flag, just like the runtime lays it out.
+///
+/// That alignment is the one of the target that this crate is compiled
+/// for, which is 8 for `u64` and `f64`. A 32 bit runtime might align them
+/// to 4 bytes instead, like Mono does on 32 bit Linux, so the value of
+/// a `long?` is at offset 4 and not 8. Read such a value with a `T` that
+/// is aligned to 4 bytes, e.g. `Nullable<[u32; 2]>`, and convert it.
+#[repr(C)]
+pub struct Nullable<T> {
+    has_value: bool,
+    value: MaybeUninit<T>,
+}
+
+impl<T: Copy> Nullable<T> {
+    /// Returns the value, or `None` if there is no value.
+    pub const fn get(&self) -> Option<T> {
+        if self.has_value {
+            // SAFETY: The value is checked to be a valid `T` when the
+            // `Nullable` is read, unless there is no value.
+            Some(unsafe { self.value.assume_init() })
+        } else {
+            None
+        }
+    }
+}
+
+const _: () = {
+    assert!(size_of::<Nullable<bool>>() == 2);
+    assert!(size_of::<Nullable<i32>>() == 8);
+    assert!(size_of::<Nullable<f64>>() == 16);
+};
+
+impl<T: Copy> From<Nullable<T>> for Option<T> {
+    fn from(nullable: Nullable<T>) -> Self {
+        nullable.get()
+    }
+}
+
+impl<T: Copy + fmt::Debug> fmt::Debug for Nullable<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.get().fmt(f)
+    }
+}
+
+// This is a manual implementation and not derived because `MaybeUninit`
+// only implements `Copy` for `T: Copy`, so the derive would not add anything.
+impl<T: Copy> ::core::marker::Copy for Nullable<T> {}
+
+// This is a manual implementation to go along with the manual `Copy`.
+impl<T: Copy> ::core::clone::Clone for Nullable<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+/// The raw bits of a [`Nullable`].
+#[doc(hidden)]
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct NullableBits<T> {
+    has_value: u8,
+    value: T,
+}
+
+// SAFETY:
+// Both fields are valid for any bit pattern.
+unsafe impl<T: AnyBitPattern> ::bytemuck::AnyBitPattern for NullableBits<T> {}
+
+// SAFETY:
+// Both fields are zeroable.
+unsafe impl<T: AnyBitPattern> ::bytemuck::Zeroable for NullableBits<T> {}
+
+// This is a manual implementation and not derived because the derive
+// macro would check the value even if there is none, which is not required.
+//
+// SAFETY:
+// `NullableBits<T::Bits>` has the same layout as `Nullable<T>`.
+// The value is only checked if there is one, otherwise it is never read.
+unsafe impl<T: CheckedBitPattern> ::bytemuck::CheckedBitPattern for Nullable<T> {
+    type Bits = NullableBits<T::Bits>;
+
+    fn is_valid_bit_pattern(bits: &Self::Bits) -> bool {
+        match bits.has_value {
+            0 => true,
+            1 => T::is_valid_bit_pattern(&bits.value),
+            _ => false,
+        }
+    }
+}
+
+/// A multi-dimensional array, e.g. `T[,]` with a `RANK` of 2.
+///
+/// The elements are stored in row-major order, the last dimension
+/// is the one whose elements are next to each other.
+#[repr(C)]
+pub struct MultiArray<T, const RANK: usize, W: PointerWidth = Bit64> {
+    _header: W::ObjectHeader,
+    bounds: W::Address,
+    size: u32,
+    _t: PhantomData<T>,
 }
 
-impl<T> Set<T> {
+impl<T, const RANK: usize, W: PointerWidth> MultiArray<T, RANK, W> {
+    const DATA: u64 = size_of::<Self>() as u64;
+
+    /// Returns the total number of elements.
     pub const fn size(&self) -> u32 {
-        self.map.size
+        self.size
+    }
+}
+
+const _: () = {
+    assert!(MultiArray::<(), 2, Bit64>::DATA == Array::<(), Bit64>::DATA);
+    assert!(MultiArray::<(), 2, Bit32>::DATA == Array::<(), Bit32>::DATA);
+};
+
+/// The length and lower bound of one dimension of a [`MultiArray`].
+#[derive(Copy, Clone)]
+#[repr(C)]
+struct ArrayBounds<W: PointerWidth> {
+    length: W::Address,
+    lower_bound: i32,
+}
+
+impl<T: CheckedBitPattern + 'static, const RANK: usize, W: PointerWidth>
+    Pointer<MultiArray<T, RANK, W>, W>
+{
+    /// Returns the length of each dimension.
+    pub fn dims<R: MemReader>(self, reader: &R) -> Result<[usize; RANK], Error> {
+        let bounds = self.bounds(reader)?;
+        Ok(bounds.map(|bounds| bounds.length.into().value() as usize))
+    }
+
+    /// Returns the lower bound of each dimension,
+    /// which is almost always 0 for arrays created in C#.
+    pub fn lower_bounds<R: MemReader>(self, reader: &R) -> Result<[i32; RANK], Error> {
+        let bounds = self.bounds(reader)?;
+        Ok(bounds.map(|bounds| bounds.lower_bound))
+    }
+
+    /// Read the element at the given index.
+    /// The index of each dimension starts at 0, regardless of its lower bound.
+    pub fn get<R: MemReader>(self, reader: &R, index: [usize; RANK]) -> Result<T, Error> {
+        let (dims, size) = self.shape(reader)?;
+        let mut offset = 0_usize;
+        for (&index, &len) in index.iter().zip(dims.iter()) {
+            if index >= len {
+                return Err(Error::index_out_of_bounds(self.addr(), index, len));
+            }
+            offset = offset
+                .checked_mul(len)
+                .and_then(|offset| offset.checked_add(index))
+                .ok_or(Error::index_out_of_bounds(self.addr(), index, len))?;
+        }
+        if offset >= size {
+            return Err(Error::index_out_of_bounds(self.addr(), offset, size));
+        }
+        let offset = self.addr() + MultiArray::<T, RANK, W>::DATA + byte_offset::<T>(offset);
+        reader.read(offset)
+    }
+
+    /// Iterate over all elements in row-major order.
+    ///
+    /// Fails if the array has more than [`MemReader::max_len`] elements.
+    pub fn iter<R: MemReader>(self, reader: &R) -> Result<ArrayIter<'_, T, R>, Error> {
+        let array = self.read(reader)?;
+        check_len(self.addr(), array.size, reader.max_len())?;
+        let start = self.addr() + MultiArray::<T, RANK, W>::DATA;
+        Ok(ArrayIter::new(reader, start, array.size as usize))
+    }
+
+    /// Iterate over the rows of the array, that is the runs of elements
+    /// along the last dimension. Each row is an iterator over its elements.
+    /// An array without elements has no rows.
+    ///
+    /// Fails if the array has more than [`MemReader::max_len`] elements.
+    pub fn rows<R: MemReader>(
+        self,
+        reader: &R,
+    ) -> Result<impl Iterator<Item = ArrayIter<'_, T, R>> + '_, Error> {
+        let (dims, size) = self.shape(reader)?;
+        let (row_len, rows) = match dims.last() {
+            Some(&0) | None => (0, 0),
+            Some(&row_len) => (row_len, size / row_len),
+        };
+        let start = self.addr() + MultiArray::<T, RANK, W>::DATA;
+        Ok((0..rows).map(move |row| {
+            let row_start = start + byte_offset::<T>(row * row_len);
+            ArrayIter::new(reader, row_start, row_len)
+        }))
+    }
+
+    /// Returns the length of each dimension and the total number of
+    /// elements, after checking that the dimensions hold exactly the
+    /// elements of the array and that there are at most
+    /// [`MemReader::max_len`] of them.
+    fn shape<R: MemReader>(self, reader: &R) -> Result<([usize; RANK], usize), Error> {
+        let array = self.read(reader)?;
+        check_len(self.addr(), array.size, reader.max_len())?;
+        let dims = self.dims(reader)?;
+        let len = dims
+            .iter()
+            .try_fold(1_usize, |len, &dim| len.checked_mul(dim));
+        if len != Some(array.size as usize) {
+            return Err(Error::invalid_bit_pattern::<MultiArray<T, RANK, W>>(
+                self.addr(),
+            ));
+        }
+        Ok((dims, array.size as usize))
+    }
+
+    fn bounds<R: MemReader>(self, reader: &R) -> Result<[ArrayBounds<W>; RANK], Error> {
+        let array = self.read(reader)?;
+        let bounds = array.bounds.into();
+        if bounds.is_null() {
+            return Err(Error::null_pointer_at::<[ArrayBounds<W>; RANK]>(
+                self.addr() + ::core::mem::offset_of!(MultiArray<T, RANK, W>, bounds) as u64,
+            ));
+        }
+        let mut buf = [<ArrayBounds<W> as ::bytemuck::Zeroable>::zeroed(); RANK];
+        reader.read_into(bounds, &mut buf)?;
+        Ok(buf)
     }
 }
 
-impl<T> fmt::Debug for Set<T> {
+impl<T, const RANK: usize, W: PointerWidth> fmt::Debug for MultiArray<T, RANK, W> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Set").field("map", &self.map).finish()
+        f.debug_struct("MultiArray")
+            .field("rank", &RANK)
+            .field("size", &self.size)
+            .field("type", &core::any::type_name::<T>())
+            .finish()
     }
 }
 
 // This is a manual implementation and not derived because the derive
 // implementation would add a `T: Copy` bound, which is not required.
-impl<T> ::core::marker::Copy for Set<T> {}
+impl<T, const RANK: usize, W: PointerWidth> ::core::marker::Copy for MultiArray<T, RANK, W> {}
 
 // This is a manual implementation and not derived because the derive
 // implementation would add a `T: Clone` bound, which is not required.
-impl<T> ::core::clone::Clone for Set<T> {
+impl<T, const RANK: usize, W: PointerWidth> ::core::clone::Clone for MultiArray<T, RANK, W> {
     fn clone(&self) -> Self {
         *self
     }
@@ -595,8 +2544,517 @@ impl<T> ::core::clone::Clone for Set<T> {
 
 // This is a manual implementation and not derived because the derive
 // macro would add a `T: AnyBitPattern` bound, which is not required.
-unsafe impl<T: 'static> ::bytemuck::AnyBitPattern for Set<T> {}
+//
+// SAFETY:
+// Same as for `Array`, the object header can be anything for our purposes.
+unsafe impl<T: 'static, const RANK: usize, W: PointerWidth> ::bytemuck::AnyBitPattern
+    for MultiArray<T, RANK, W>
+{
+}
 
 // This is a manual implementation and not derived because the derive
 // macro would add a `T: Zeroable` bound, which is not required.
-unsafe impl<T: 'static> ::bytemuck::Zeroable for Set<T> {}
+unsafe impl<T: 'static, const RANK: usize, W: PointerWidth> ::bytemuck::Zeroable
+    for MultiArray<T, RANK, W>
+{
+}
+
+// This is a manual implementation and not derived because the derive
+// macro would require `W: AnyBitPattern`, which the pointer widths do
+// not implement, as they are never read from memory themselves.
+//
+// SAFETY:
+// Both fields are valid for any bit pattern.
+unsafe impl<W: PointerWidth> ::bytemuck::AnyBitPattern for ArrayBounds<W> {}
+
+// This is a manual implementation and not derived because the derive
+// macro would require `W: Zeroable`, which the pointer widths do not
+// implement, as they are never read from memory themselves.
+unsafe impl<W: PointerWidth> ::bytemuck::Zeroable for ArrayBounds<W> {}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use core::mem::{offset_of, size_of};
+
+    use alloc::{string::String, vec::Vec};
+
+    use super::*;
+    use crate::fake::FakeHeap;
+
+    #[test]
+    fn null_fields_of_derived_classes_are_null_pointers() {
+        let mut heap = FakeHeap::new();
+        heap.write(0x1000_u64, 0x2000_u64).write(0x1008_u64, 0_u64);
+
+        let target = NonNullPointer::<u32>::__read_field(&heap, Address::new(0x1000)).unwrap();
+        assert_eq!(target.address(), Address64::new(0x2000));
+        assert_eq!(
+            NonNullPointer::<u32>::__read_field(&heap, Address::new(0x1008)).err(),
+            Some(Error::null_pointer_at::<u32>(Address::new(0x1008)))
+        );
+        assert_eq!(
+            alloc::format!("{}", Error::null_pointer_at::<u32>(0x1008_u64)),
+            "tried to follow a null pointer to `u32` stored at 0x1008"
+        );
+        assert_eq!(
+            alloc::format!("{}", Error::null_pointer::<u32>()),
+            "tried to follow a null pointer to `u32`"
+        );
+        assert!(matches!(
+            heap.read::<NonNullPointer<u32>, _>(0x1008_u64),
+            Err(Error::InvalidBitPattern { .. })
+        ));
+    }
+
+    #[test]
+    fn iter_returns_an_error_for_every_unreadable_element() {
+        let mut heap = FakeHeap::new();
+        let list = heap.list(0x1000_u64, 0x2000_u64, &[1_u32, 2, 3]);
+        // The list claims more items than the page of the array holds,
+        // so the last items cannot be read.
+        let len = 2000;
+        heap.write(0x2000 + offset_of!(Array<u32>, size) as u64, len)
+            .write(0x1000 + offset_of!(List<u32>, size) as u64, len);
+        let readable = ((0x1000 - Array::<u32>::DATA) / 4) as usize;
+
+        let mut iter = list.iter(&heap).unwrap();
+        assert_eq!(iter.len(), 2000);
+        assert_eq!(iter.next().unwrap().unwrap(), 1);
+        assert_eq!(iter.len(), 1999);
+        assert!(iter.next_back().unwrap().is_err());
+        assert_eq!(iter.len(), 1998);
+        assert_eq!(iter.nth(1).unwrap().unwrap(), 3);
+        assert_eq!(iter.len(), 1996);
+
+        let items = list.iter(&heap).unwrap().collect::<Vec<_>>();
+        assert_eq!(items.len(), 2000);
+        assert!(items[..readable].iter().all(Result::is_ok));
+        assert!(items[readable..].iter().all(Result::is_err));
+    }
+
+    #[test]
+    fn max_len_applies_to_the_size_of_collections() {
+        let mut heap = FakeHeap::new();
+        let items = (0..90).collect::<Vec<u32>>();
+        let entries = items.iter().map(|&i| (i, i)).collect::<Vec<_>>();
+        let list = heap.list(0x1000_u64, 0x2000_u64, &items);
+        let map = heap.map(0x3000_u64, 0x4000_u64, &entries);
+        // Give both collections spare capacity, like a prime capacity
+        // of a dictionary with 90 entries.
+        heap.write(0x2000 + offset_of!(Array<u32>, size) as u64, 107_u32)
+            .write(0x4000 + offset_of!(Array<u32>, size) as u64, 107_u32);
+
+        let reader = LimitedReader::new(&heap, 100);
+        assert_eq!(list.iter(&reader).unwrap().count(), 90);
+        assert_eq!(list.to_vec(&reader).unwrap(), items);
+        assert_eq!(map.iter(&reader).unwrap().count(), 90);
+
+        let reader = LimitedReader::new(&heap, 80);
+        assert!(matches!(list.to_vec(&reader), Err(Error::TooLong { .. })));
+        assert!(matches!(map.iter(&reader), Err(Error::TooLong { .. })));
+
+        // More entries than the entries array can hold.
+        heap.write(0x4000 + offset_of!(Array<u32>, size) as u64, 10_u32);
+        assert!(matches!(
+            map.iter(&heap),
+            Err(Error::InvalidLength {
+                len: 90,
+                capacity: 10,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn multi_array_checks_its_dimensions() {
+        let mut heap = FakeHeap::new();
+        let items = (0..6).collect::<Vec<u16>>();
+        let array = heap.multi_array(0x1000_u64, 0x2000_u64, [2, 3], &items);
+        assert_eq!(array.get(&heap, [1, 2]).unwrap(), 5);
+        assert_eq!(array.rows(&heap).unwrap().count(), 2);
+
+        // Dimensions whose product overflows.
+        heap.write(0x2000_u64, u64::MAX)
+            .write(0x2000 + size_of::<ArrayBounds<Bit64>>() as u64, u64::MAX);
+        assert!(matches!(
+            array.get(&heap, [1, 2]),
+            Err(Error::InvalidBitPattern { .. })
+        ));
+        assert!(array.rows(&heap).is_err());
+
+        // Dimensions that do not match the number of elements.
+        heap.write(0x2000_u64, 2_u64)
+            .write(0x2000 + size_of::<ArrayBounds<Bit64>>() as u64, 4_u64);
+        assert!(array.get(&heap, [1, 3]).is_err());
+        assert!(array.rows(&heap).is_err());
+    }
+
+    #[test]
+    fn nullable_with_a_value_aligned_to_4_bytes() {
+        let mut heap = FakeHeap::new();
+        heap.write(0x1000_u64, 1_u32).write(0x1004_u64, 7_u64);
+        let value = heap.read::<Nullable<[u32; 2]>, _>(0x1000_u64).unwrap();
+        let value = value.get().map(bytemuck::cast::<_, u64>);
+        assert_eq!(value, Some(7));
+
+        heap.write(0x1000_u64, 0_u32);
+        let value = heap.read::<Nullable<[u32; 2]>, _>(0x1000_u64).unwrap();
+        assert_eq!(value.get(), None);
+    }
+
+    #[test]
+    fn iter_reads_the_readable_elements_of_a_chunk() {
+        let mut heap = FakeHeap::new();
+        // The array ends on the next page, which is never written to.
+        let items = (0..32).collect::<Vec<u64>>();
+        let array = heap.array(0x1F00 - Array::<u64>::DATA, &items);
+        heap.write(array.addr() + offset_of!(Array<u64>, size) as u64, 64_u32);
+
+        let read = array.iter(&heap).unwrap().collect::<Vec<_>>();
+        assert_eq!(read.len(), 64);
+        assert!(read[..32]
+            .iter()
+            .zip(&items)
+            .all(|(a, b)| a.as_ref() == Ok(b)));
+        assert!(read[32..]
+            .iter()
+            .all(|item| matches!(item, Err(Error::Unreadable { .. }))));
+
+        // The same from the back, which fails right away.
+        let mut iter = array.iter(&heap).unwrap();
+        assert!(iter.next_back().unwrap().is_err());
+        assert_eq!(iter.next().unwrap(), Ok(0));
+        assert_eq!(iter.len(), 62);
+    }
+
+    #[test]
+    fn iter_reads_large_elements_one_by_one() {
+        let mut heap = FakeHeap::new();
+        let items = [[1_u64; 128], [2; 128], [3; 128]];
+        let array = heap.array(0x1000_u64, &items);
+        let read = array.iter(&heap).unwrap().rev().collect::<Vec<_>>();
+        assert_eq!(read, [Ok([3; 128]), Ok([2; 128]), Ok([1; 128])]);
+    }
+
+    #[test]
+    fn strings_are_compared_without_copying() {
+        let mut heap = FakeHeap::new();
+        let s = heap.string(0x1000_u64, "Grüße 😀");
+        assert!(s.eq_str(&heap, "Grüße 😀").unwrap());
+        assert!(!s.eq_str(&heap, "Grüsse 😀").unwrap());
+        // Same number of UTF-16 code units, different low surrogate.
+        assert!(!s.eq_str(&heap, "Grüße 😁").unwrap());
+        assert!(!s.eq_str(&heap, "Grüße").unwrap());
+
+        assert!(s.starts_with(&heap, "").unwrap());
+        assert!(s.starts_with(&heap, "Grüße ").unwrap());
+        assert!(s.starts_with(&heap, "Grüße 😀").unwrap());
+        assert!(!s.starts_with(&heap, "Grüße 😀!").unwrap());
+        assert!(!s.starts_with(&heap, "Grüsse").unwrap());
+
+        assert!(s
+            .eq_ignore_ascii_case(&heap, "gRÜSSE 😀")
+            .is_ok_and(|eq| !eq));
+        assert!(s.eq_ignore_ascii_case(&heap, "gRüßE 😀").unwrap());
+    }
+
+    #[test]
+    fn strings_are_hashed_as_utf16() {
+        let mut heap = FakeHeap::new();
+        let empty = heap.string(0x1000_u64, "");
+        let hello = heap.string(0x2000_u64, "Hello");
+        let emoji = heap.string(0x3000_u64, "é😀");
+        // FNV-1a 64 of the UTF-16LE bytes of each string.
+        assert_eq!(hash_utf16(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash_utf16("Hello"), 0xb050_3845_5063_40b7);
+        assert_eq!(hash_utf16("é😀"), 0xd8d1_b753_fa69_1777);
+        assert_eq!(empty.hash_utf16(&heap).unwrap(), hash_utf16(""));
+        assert_eq!(hello.hash_utf16(&heap).unwrap(), hash_utf16("Hello"));
+        assert_eq!(emoji.hash_utf16(&heap).unwrap(), hash_utf16("é😀"));
+    }
+
+    #[test]
+    fn checked_strings_report_what_does_not_fit() {
+        let mut heap = FakeHeap::new();
+        let s = heap.string(0x1000_u64, "Hello, 世界!");
+        assert_eq!(
+            s.to_string_checked::<_, 32>(&heap).unwrap().as_str(),
+            "Hello, 世界!"
+        );
+        assert_eq!(s.to_string::<_, 8>(&heap).unwrap().as_str(), "Hello, ");
+        assert_eq!(
+            s.to_string_checked::<_, 8>(&heap).err(),
+            Some(Error::Truncated {
+                address: Address::new(0x1000),
+                capacity: 8,
+            })
+        );
+
+        // The string has 10 UTF-16 code units.
+        let reader = LimitedReader::new(&heap, 9);
+        assert!(matches!(
+            s.to_string_checked::<_, 32>(&reader),
+            Err(Error::TooLong { .. })
+        ));
+        assert!(matches!(s.hash_utf16(&reader), Err(Error::TooLong { .. })));
+    }
+
+    #[test]
+    fn string_builder_chunks() {
+        let mut heap = FakeHeap::new();
+        let builder = heap.string_builder(0x1000_u64, 0x2000_u64, &["Hello", ", ", "世界!"]);
+        assert_eq!(builder.len(&heap).unwrap(), 10);
+        assert_eq!(builder.to_std_string(&heap).unwrap(), "Hello, 世界!");
+
+        // Chunks that are longer than what is read at once.
+        let long = ["a".repeat(300), "b".repeat(300), "c".repeat(300)];
+        let chunks = long.iter().map(String::as_str).collect::<Vec<_>>();
+        let builder = heap.string_builder(0x7000_u64, 0x8000_u64, &chunks);
+        assert_eq!(builder.to_std_string(&heap).unwrap(), long.concat());
+
+        let builder = heap.string_builder(0x3000_u64, 0x4000_u64, &["a", "", "b", ""]);
+        assert_eq!(builder.to_std_string(&heap).unwrap(), "ab");
+
+        let builder = heap.string_builder(0x5000_u64, 0x6000_u64, &[]);
+        assert_eq!(builder.to_std_string(&heap).unwrap(), "");
+    }
+
+    #[test]
+    fn string_builder_with_cyclic_chunks() {
+        let mut heap = FakeHeap::new();
+        let builder = heap.string_builder(0x1000_u64, 0x2000_u64, &["ab", "", ""]);
+        // The empty chunks have the same offset and point to each other.
+        let middle = builder.read(&heap).unwrap().chunk_previous;
+        let previous = offset_of!(StringBuilder<Bit64>, chunk_previous) as u64;
+        heap.write(middle.addr() + previous, builder.address());
+
+        assert!(matches!(
+            builder.to_std_string(&heap),
+            Err(Error::InvalidBitPattern { .. })
+        ));
+
+        // A chunk that points to a later chunk.
+        let builder = heap.string_builder(0x3000_u64, 0x4000_u64, &["ab", "cd"]);
+        let first = builder.read(&heap).unwrap().chunk_previous;
+        let offset = offset_of!(StringBuilder<Bit64>, chunk_offset) as u64;
+        heap.write(first.addr() + previous, builder.address())
+            .write(first.addr() + offset, 1_u32);
+        assert!(matches!(
+            builder.to_std_string(&heap),
+            Err(Error::InvalidBitPattern { .. })
+        ));
+    }
+
+    fn decode(bytes: &[u8], encoding: Encoding) -> String {
+        DecodeBytes::new(bytes.iter().map(|&b| Ok(b)), encoding)
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    #[test]
+    fn decode_bytes_like_from_utf8_lossy() {
+        let cases: [&[u8]; 12] = [
+            b"plain",
+            "gr\u{fc}\u{df}e \u{1F600}".as_bytes(),
+            // Truncated sequences.
+            b"\xE2\x82",
+            b"a\xF0\x9F\x98b",
+            b"\xF0\x9F",
+            // Overlong encodings.
+            b"\xC0\xAF",
+            b"\xE0\x80\xAF",
+            b"\xF0\x80\x80\xAF",
+            // Surrogates and code points above U+10FFFF.
+            b"\xED\xA0\x80\xED\xBF\xBF",
+            b"\xF4\x90\x80\x80",
+            // Stray continuation bytes and bytes that never occur.
+            b"\x80\xBF",
+            b"\xFE\xFF\xC1",
+        ];
+        for bytes in cases {
+            let expected = String::from_utf8_lossy(bytes);
+            assert_eq!(decode(bytes, Encoding::Utf8), expected, "{bytes:x?}");
+        }
+
+        let mut bytes = [0; 3];
+        for first in 0..=255 {
+            for second in 0..=255 {
+                bytes[..2].copy_from_slice(&[first, second]);
+                let expected = String::from_utf8_lossy(&bytes[..2]);
+                assert_eq!(decode(&bytes[..2], Encoding::Utf8), expected);
+            }
+        }
+        for first in 0xE0..=0xF4 {
+            for second in 0x7F..=0xC0 {
+                for third in 0..=255 {
+                    bytes = [first, second, third];
+                    let expected = String::from_utf8_lossy(&bytes);
+                    assert_eq!(decode(&bytes, Encoding::Utf8), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn decode_latin1_bytes() {
+        let bytes = (0..=255).collect::<Vec<u8>>();
+        let expected = bytes.iter().map(|&b| char::from(b)).collect::<String>();
+        assert_eq!(decode(&bytes, Encoding::Latin1), expected);
+    }
+
+    #[test]
+    fn map_get_follows_collision_chains() {
+        let mut heap = FakeHeap::new();
+        let entries = (0..20).map(|i| (i * 20, i)).collect::<Vec<(i32, i32)>>();
+        // All keys are in the first of 20 buckets.
+        let map = heap.map(0x1000_u64, 0x2000_u64, &entries);
+        for (key, value) in &entries {
+            assert_eq!(map.get(&heap, key).unwrap(), Some(*value));
+            assert!(map.contains_key(&heap, key).unwrap());
+        }
+        assert_eq!(map.get(&heap, &1).unwrap(), None);
+
+        // Different hash codes in the same bucket.
+        let map = heap.map(0x8000_u64, 0x9000_u64, &[(1_u32, 10_u8), (4, 40), (7, 70)]);
+        assert_eq!(map.get(&heap, &4).unwrap(), Some(40));
+        assert_eq!(map.get(&heap, &10).unwrap(), None);
+    }
+
+    #[test]
+    fn map_get_detects_cyclic_chains() {
+        let mut heap = FakeHeap::new();
+        let map = heap.map(0x1000_u64, 0x2000_u64, &[(1_u32, 1_u32), (2, 2)]);
+        let entry = 0x2000 + Array::<Entry<u32, u32>>::DATA;
+        heap.write(entry + offset_of!(Entry<u32, u32>, next) as u64, 0_i32);
+        assert!(matches!(
+            map.get(&heap, &3),
+            Err(Error::InvalidBitPattern { .. })
+        ));
+    }
+
+    #[test]
+    fn set_contains_reads_one_based_buckets() {
+        let mut heap = FakeHeap::new();
+        let set = heap.set(0x1000_u64, 0x2000_u64, &[0_u16, 1, 2, 5]);
+        for item in [0, 1, 2, 5] {
+            assert!(set.contains(&heap, &item).unwrap());
+        }
+        assert!(!set.contains(&heap, &3).unwrap());
+
+        // The same buckets as a dictionary point one entry too far,
+        // past the last entry for the last item.
+        let map = Pointer::<Map<u16, ()>>::new(set.address());
+        assert_eq!(
+            map.contains_key(&heap, &5),
+            Err(Error::index_out_of_bounds(0x2000_u64, 4, 4))
+        );
+    }
+
+    #[test]
+    fn pointers_project_fields_and_elements() {
+        let mut heap = FakeHeap::new();
+        let list = heap.list(0x1000_u64, 0x2000_u64, &[1_u32, 2, 3]);
+        heap.write(0x3000_u64, list);
+
+        let items = list.field::<Pointer<Array<u32>>>(offset_of!(List<u32>, items) as u64);
+        assert_eq!(items.address(), Address64::new(0x1010));
+        let first = items.deref(&heap).unwrap().field::<u32>(Array::<u32>::DATA);
+        assert_eq!(first.read(&heap), Ok(1));
+        assert_eq!(first.add(2).read(&heap), Ok(3));
+        assert_eq!(first.add(2).address(), Address64::new(0x2028));
+
+        let stored = Pointer::<Pointer<List<u32>>>::new(Address64::new(0x3000));
+        assert_eq!(stored.deref(&heap).unwrap().address(), list.address());
+
+        // Null pointers stay null and huge counts wrap around.
+        let null = Pointer::<u32>::new(Address64::NULL);
+        assert!(null.field::<u32>(8).add(1).address().is_null());
+        assert_eq!(first.add(usize::MAX).address(), Address64::new(0x201C));
+        assert_eq!(
+            Pointer::<Pointer<u32>>::new(Address64::NULL)
+                .deref(&heap)
+                .err(),
+            Some(Error::null_pointer::<Pointer<u32>>())
+        );
+    }
+
+    #[test]
+    fn pointers_and_arrays_write_what_is_read() {
+        let mut heap = FakeHeap::new();
+        let array = heap.array(0x1000_u64, &[1_u32, 2, 3]);
+        array.set(&mut heap, 2, 30).unwrap();
+        assert_eq!(array.to_vec(&heap).unwrap(), [1, 2, 30]);
+        assert_eq!(
+            array.set(&mut heap, 3, 40),
+            Err(Error::index_out_of_bounds(0x1000_u64, 3, 3))
+        );
+        // The element after the end is left untouched.
+        assert_eq!(heap.read::<u32, _>(0x1000 + Array::<u32>::DATA + 12), Ok(0));
+
+        let value = Pointer::<u64>::new(Address64::new(0x2000));
+        value.write(&mut heap, 0xDEAD_BEEF).unwrap();
+        assert_eq!(value.read(&heap), Ok(0xDEAD_BEEF));
+        assert_eq!(
+            Pointer::<u64>::new(Address64::NULL).write(&mut heap, 1),
+            Err(Error::null_pointer::<u64>())
+        );
+    }
+
+    #[test]
+    fn map_iterates_zero_keys() {
+        let mut heap = FakeHeap::new();
+        // Both keys are in the first bucket, so the entry of `0` has a
+        // hash code of 0 and links to the entry at index 0.
+        let map = heap.map(0x1000_u64, 0x2000_u64, &[(2_u32, 20_u32), (0, 0)]);
+        let entry = 0x2000 + Array::<Entry<u32, u32>>::DATA + size_of::<Entry<u32, u32>>() as u64;
+        assert_eq!(heap.read::<Entry<u32, u32>, _>(entry).unwrap().next, 0);
+
+        let entries = map.iter(&heap).unwrap().collect::<Result<Vec<_>, _>>();
+        assert_eq!(entries.unwrap(), [(2, 20), (0, 0)]);
+        assert_eq!(map.get(&heap, &0).unwrap(), Some(0));
+    }
+
+    #[test]
+    fn set_iterates_past_removed_items() {
+        let mut heap = FakeHeap::new();
+        let set = heap.set(0x1000_u64, 0x2000_u64, &[1_u32, 2, 3, 4]);
+
+        // Remove 2 like `HashSet.Remove` does, which leaves a gap in the
+        // slots and only decrements the count.
+        let slot = 0x2000 + Array::<Entry<u32, ()>>::DATA + size_of::<Entry<u32, ()>>() as u64;
+        heap.write(slot, -1_i32)
+            .write(slot + offset_of!(Entry<u32, ()>, key) as u64, 0_u32)
+            .write(0x1000 + offset_of!(Set<u32>, count) as u64, 3_u32);
+
+        assert_eq!(set.read(&heap).unwrap().size(), 3);
+        let items = set.iter(&heap).unwrap().collect::<Result<Vec<_>, _>>();
+        assert_eq!(items.unwrap(), [1, 3, 4]);
+        let items = set
+            .iter(&heap)
+            .unwrap()
+            .rev()
+            .collect::<Result<Vec<_>, _>>();
+        assert_eq!(items.unwrap(), [4, 3, 1]);
+    }
+
+    #[test]
+    fn map_skips_removed_entries() {
+        let mut heap = FakeHeap::new();
+        let a = heap.string(0x1000_u64, "a");
+        let b = heap.string(0x1100_u64, "b");
+        let map = heap.map_with_hash(0x2000_u64, 0x3000_u64, &[(a, 1_u32), (b, 2)], |_| 1);
+
+        // Remove "a" like `Dictionary.Remove` does.
+        let entry = 0x3000 + Array::<Entry<Pointer<CSString>, u32>>::DATA;
+        heap.write(entry, -1_i32)
+            .write(entry + size_of::<u32>() as u64, -1_i32)
+            .write(
+                entry + offset_of!(Entry<Pointer<CSString>, u32>, key) as u64,
+                0_u64,
+            );
+
+        assert_eq!(map.get_by_str(&heap, "a").unwrap(), None);
+        assert_eq!(map.get_by_str(&heap, "b").unwrap(), Some(2));
+        assert_eq!(map.iter(&heap).unwrap().count(), 1);
+    }
+}
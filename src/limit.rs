@@ -0,0 +1,73 @@
+use asr::Address;
+use bytemuck::CheckedBitPattern;
+
+use crate::{Backend, BackendReader, Error, MemReader};
+
+/// A [`MemReader`] that overrides the [maximum length](MemReader::max_len)
+/// of the collections that are iterated through it.
+///
+/// ```no_run
+/// use asr::Address64;
+/// use csharp_mem::{Array, Error, LimitedReader, MemReader, Pointer};
+///
+/// fn total(reader: &impl MemReader, scores: Address64) -> Result<u32, Error> {
+///     // There are never more than 100 scores, anything longer is garbage.
+///     let reader = LimitedReader::new(reader, 100);
+///     Pointer::<Array<u32>>::new(scores).iter(&reader)?.sum()
+/// }
+/// ```
+pub struct LimitedReader<R> {
+    reader: R,
+    max_len: usize,
+}
+
+impl<R: MemReader> LimitedReader<R> {
+    /// Wrap the reader and refuse to iterate collections
+    /// with more than `max_len` elements.
+    pub const fn new(reader: R, max_len: usize) -> Self {
+        Self { reader, max_len }
+    }
+
+    /// Returns the wrapped reader.
+    pub const fn reader(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns the wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Change the maximum length of collections.
+    pub fn set_max_len(&mut self, max_len: usize) {
+        self.max_len = max_len;
+    }
+}
+
+impl<R: MemReader> MemReader for LimitedReader<R> {
+    fn read<T: CheckedBitPattern, A: Into<Address>>(&self, addr: A) -> Result<T, Error> {
+        self.reader.read(addr)
+    }
+
+    fn read_bytes<A: Into<Address>>(&self, addr: A, buf: &mut [u8]) -> Result<(), Error> {
+        self.reader.read_bytes(addr, buf)
+    }
+
+    fn read_into<T: CheckedBitPattern, A: Into<Address>>(
+        &self,
+        addr: A,
+        buf: &mut [T],
+    ) -> Result<(), Error> {
+        self.reader.read_into(addr, buf)
+    }
+
+    fn max_len(&self) -> usize {
+        self.max_len
+    }
+}
+
+impl<R: BackendReader> BackendReader for LimitedReader<R> {
+    fn backend(&self) -> Backend {
+        self.reader.backend()
+    }
+}
@@ -0,0 +1,227 @@
+//! Reading the memory of a native Linux process.
+//!
+//! A [`LinuxProcess`] reads through `/proc/<pid>/mem` and implements
+//! [`MemReader`], as well as [`MemWriter`] if it has been attached with
+//! [`LinuxProcess::attach_writable`], so that the same [`Pointer`](crate::Pointer) and binding
+//! code that runs inside of LiveSplit can run in a native tool or in
+//! integration tests against a helper process.
+//!
+//! Reading the memory of another process requires the same permissions
+//! as attaching a debugger to it, see `ptrace(2)`. Usually that means
+//! running as the same user and either being the parent of the process
+//! or having `/proc/sys/kernel/yama/ptrace_scope` set to `0`.
+//!
+//! ```no_run
+//! use csharp_mem::{linux::LinuxProcess, MemReader};
+//!
+//! let process = LinuxProcess::attach(1234)?;
+//! let module = process.module("UnityPlayer.so")?.expect("not a Unity game");
+//! let magic = process.read::<[u8; 4], _>(module.base)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io,
+    os::unix::fs::FileExt,
+    path::PathBuf,
+    string::String,
+    vec::Vec,
+};
+
+use asr::Address;
+use bytemuck::CheckedBitPattern;
+
+use crate::{Error, MemReader, MemWriter};
+
+/// A process on the local Linux machine.
+pub struct LinuxProcess {
+    pid: u32,
+    mem: File,
+}
+
+/// A file that is mapped into the memory of a [`LinuxProcess`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Module {
+    /// The file name of the module, e.g. `UnityPlayer.so`.
+    pub name: String,
+    /// The full path of the mapped file.
+    pub path: PathBuf,
+    /// The address of the first mapping of the module.
+    pub base: Address,
+    /// The number of bytes from the base to the end of the last mapping.
+    pub size: u64,
+}
+
+impl LinuxProcess {
+    /// Attach to the process with the given pid.
+    pub fn attach(pid: u32) -> io::Result<Self> {
+        let mem = File::open(std::format!("/proc/{pid}/mem"))?;
+        Ok(Self { pid, mem })
+    }
+
+    /// Attach to the process with the given pid,
+    /// so that its memory can also be written to.
+    pub fn attach_writable(pid: u32) -> io::Result<Self> {
+        let mem = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(std::format!("/proc/{pid}/mem"))?;
+        Ok(Self { pid, mem })
+    }
+
+    /// Returns the pid of the process.
+    pub const fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Returns all files that are mapped into the memory of the process,
+    /// in the order of their base addresses.
+    pub fn modules(&self) -> io::Result<Vec<Module>> {
+        let maps = fs::read_to_string(std::format!("/proc/{}/maps", self.pid))?;
+        let mut modules = Vec::<Module>::new();
+        for line in maps.lines() {
+            let Some((start, end, path)) = parse_mapping(line) else {
+                continue;
+            };
+            // Pseudo mappings like `[heap]` or `[stack]` have no path.
+            if !path.starts_with('/') {
+                continue;
+            }
+
+            match modules
+                .iter_mut()
+                .find(|module| module.path.as_os_str() == path)
+            {
+                Some(module) => {
+                    let base = module.base.value().min(start);
+                    let end = (module.base.value() + module.size).max(end);
+                    module.base = Address::new(base);
+                    module.size = end - base;
+                }
+                None => {
+                    let path = PathBuf::from(path);
+                    let name = path
+                        .file_name()
+                        .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+                    modules.push(Module {
+                        name,
+                        path,
+                        base: Address::new(start),
+                        size: end - start,
+                    });
+                }
+            }
+        }
+        modules.sort_by_key(|module| module.base.value());
+        Ok(modules)
+    }
+
+    /// Returns the module with the given file name, e.g. `UnityPlayer.so`.
+    pub fn module(&self, name: &str) -> io::Result<Option<Module>> {
+        Ok(self
+            .modules()?
+            .into_iter()
+            .find(|module| module.name == name))
+    }
+}
+
+impl MemReader for LinuxProcess {
+    fn read<T: CheckedBitPattern, A: Into<Address>>(&self, addr: A) -> Result<T, Error> {
+        let addr = addr.into();
+        crate::read_bits(addr, |buf| {
+            self.read_bytes(addr, buf)
+                .map_err(|_| Error::unreadable::<T>(addr))
+        })
+    }
+
+    fn read_bytes<A: Into<Address>>(&self, addr: A, buf: &mut [u8]) -> Result<(), Error> {
+        let addr = addr.into();
+        self.mem
+            .read_exact_at(buf, addr.value())
+            .map_err(|_| Error::unreadable_bytes(addr, buf.len()))
+    }
+
+    fn read_into<T: CheckedBitPattern, A: Into<Address>>(
+        &self,
+        addr: A,
+        buf: &mut [T],
+    ) -> Result<(), Error> {
+        crate::read_into_chunked(self, addr, buf)
+    }
+}
+
+impl MemWriter for LinuxProcess {
+    fn write_bytes<A: Into<Address>>(&mut self, addr: A, bytes: &[u8]) -> Result<(), Error> {
+        let addr = addr.into();
+        self.mem
+            .write_all_at(bytes, addr.value())
+            .map_err(|_| Error::unwritable_bytes(addr, bytes.len()))
+    }
+}
+
+/// Parses a line of `/proc/<pid>/maps`, which looks like
+/// `7f0c4e400000-7f0c4e422000 r--p 00000000 08:01 1234 /usr/lib/libc.so.6`.
+fn parse_mapping(line: &str) -> Option<(u64, u64, &str)> {
+    let mut parts = line.splitn(6, ' ');
+    let (start, end) = parts.next()?.split_once('-')?;
+    let start = u64::from_str_radix(start, 16).ok()?;
+    let end = u64::from_str_radix(end, 16).ok()?;
+    let path = parts.nth(4).unwrap_or_default().trim_start();
+    Some((start, end, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static MAGIC: [u8; 8] = *b"csharpmm";
+
+    #[test]
+    fn parses_mappings() {
+        assert_eq!(
+            parse_mapping("7f0c4e400000-7f0c4e422000 r--p 00000000 08:01 1234 /usr/lib/libc.so.6"),
+            Some((0x7f0c_4e40_0000, 0x7f0c_4e42_2000, "/usr/lib/libc.so.6"))
+        );
+        assert_eq!(
+            parse_mapping("00400000-00401000 r-xp 00000000 08:01 42        /opt/My Game/game"),
+            Some((0x40_0000, 0x40_1000, "/opt/My Game/game"))
+        );
+        assert_eq!(
+            parse_mapping("7ffd1000-7ffd2000 rw-p 00000000 00:00 0                  [stack]"),
+            Some((0x7ffd_1000, 0x7ffd_2000, "[stack]"))
+        );
+        assert_eq!(
+            parse_mapping("7f00-7f10 rw-p 00000000 00:00 0"),
+            Some((0x7f00, 0x7f10, ""))
+        );
+        assert_eq!(parse_mapping(""), None);
+        assert_eq!(parse_mapping("not-hex r--p"), None);
+    }
+
+    #[test]
+    fn reads_its_own_memory() {
+        let process = LinuxProcess::attach(std::process::id()).unwrap();
+        let addr = Address::new(MAGIC.as_ptr() as u64);
+        assert_eq!(process.read::<[u8; 8], _>(addr), Ok(MAGIC));
+        assert!(process.read::<u8, _>(Address::NULL).is_err());
+
+        // The static is part of the test binary.
+        let exe = std::env::current_exe().unwrap();
+        let modules = process.modules().unwrap();
+        let module = modules.iter().find(|module| module.path == exe).unwrap();
+        assert!((module.base.value()..module.base.value() + module.size).contains(&addr.value()));
+    }
+
+    #[test]
+    fn writes_its_own_memory() {
+        use core::sync::atomic::{AtomicU64, Ordering};
+
+        let value = AtomicU64::new(1);
+        let addr = Address::new(value.as_ptr() as u64);
+        let mut process = LinuxProcess::attach_writable(std::process::id()).unwrap();
+        process.write(addr, 2_u64).unwrap();
+        assert_eq!(process.read::<u64, _>(addr), Ok(2));
+        assert_eq!(value.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,467 @@
+//! Recording and replaying of memory reads.
+//!
+//! A [`Recorder`] wraps any [`MemReader`] and logs every read, grouped
+//! by tick. The resulting [`Recording`] can be encoded into a compact
+//! binary format with [`Recording::to_bytes`], stored in a file, and
+//! later be served back by a [`Replay`], which is a [`MemReader`] itself.
+//! This allows reproducing the exact memory state of a tick offline.
+//!
+//! ```
+//! use csharp_mem::{
+//!     fake::FakeHeap,
+//!     record::{Recorder, Recording, Replay},
+//! };
+//!
+//! let mut heap = FakeHeap::new();
+//! let name = heap.string(0x1000, "Level 1");
+//!
+//! let mut recorder = Recorder::new(&heap);
+//! assert_eq!(name.to_std_string(&recorder).unwrap(), "Level 1");
+//! let bytes = recorder.into_recording().to_bytes();
+//!
+//! let replay = Replay::new(Recording::from_bytes(&bytes).unwrap());
+//! assert_eq!(name.to_std_string(&replay).unwrap(), "Level 1");
+//! ```
+
+use alloc::{vec, vec::Vec};
+use core::{cell::RefCell, fmt, mem};
+
+use asr::Address;
+use bytemuck::CheckedBitPattern;
+
+use crate::{Backend, BackendReader, Error, MemReader};
+
+const MAGIC: [u8; 4] = *b"CSMR";
+const VERSION: u8 = 1;
+
+/// A [`MemReader`] that records every read of the wrapped reader.
+pub struct Recorder<R> {
+    reader: R,
+    recording: RefCell<Recording>,
+}
+
+impl<R: MemReader> Recorder<R> {
+    /// Start recording the reads of the given reader.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            recording: RefCell::new(Recording {
+                ticks: vec![Vec::new()],
+            }),
+        }
+    }
+
+    /// Returns the reader whose reads are recorded.
+    pub const fn reader(&self) -> &R {
+        &self.reader
+    }
+
+    /// Start a new tick. All following reads are recorded as part of it.
+    pub fn next_tick(&mut self) {
+        self.recording.get_mut().ticks.push(Vec::new());
+    }
+
+    /// Take everything that has been recorded so far and start over
+    /// with a new, empty recording.
+    pub fn take_recording(&mut self) -> Recording {
+        mem::replace(
+            self.recording.get_mut(),
+            Recording {
+                ticks: vec![Vec::new()],
+            },
+        )
+    }
+
+    /// Stop recording and return everything that has been recorded.
+    pub fn into_recording(self) -> Recording {
+        self.recording.into_inner()
+    }
+}
+
+impl<R: MemReader> MemReader for Recorder<R> {
+    fn read<T: CheckedBitPattern, A: Into<Address>>(&self, addr: A) -> Result<T, Error> {
+        let addr = addr.into();
+        crate::read_bits(addr, |buf| {
+            self.read_bytes(addr, buf)
+                .map_err(|_| Error::unreadable::<T>(addr))
+        })
+    }
+
+    fn read_bytes<A: Into<Address>>(&self, addr: A, buf: &mut [u8]) -> Result<(), Error> {
+        let addr = addr.into();
+        let result = self.reader.read_bytes(addr, buf);
+        let read = Read {
+            address: addr.value(),
+            len: buf.len(),
+            bytes: result.is_ok().then(|| buf.to_vec()),
+        };
+        if let Some(tick) = self.recording.borrow_mut().ticks.last_mut() {
+            tick.push(read);
+        }
+        result
+    }
+
+    fn read_into<T: CheckedBitPattern, A: Into<Address>>(
+        &self,
+        addr: A,
+        buf: &mut [T],
+    ) -> Result<(), Error> {
+        crate::read_into_chunked(self, addr, buf)
+    }
+
+    fn max_len(&self) -> usize {
+        self.reader.max_len()
+    }
+}
+
+impl<R: BackendReader> BackendReader for Recorder<R> {
+    fn backend(&self) -> Backend {
+        self.reader.backend()
+    }
+}
+
+/// The reads of a [`Recorder`], grouped by tick.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Recording {
+    ticks: Vec<Vec<Read>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Read {
+    address: u64,
+    len: usize,
+    /// `None` if the read failed.
+    bytes: Option<Vec<u8>>,
+}
+
+impl Recording {
+    /// Returns the number of recorded ticks.
+    pub fn ticks(&self) -> usize {
+        self.ticks.len()
+    }
+
+    /// Returns the number of reads in the given tick.
+    pub fn reads(&self, tick: usize) -> usize {
+        self.ticks.get(tick).map_or(0, Vec::len)
+    }
+
+    /// Encode the recording into its binary format.
+    ///
+    /// The format starts with the magic bytes `CSMR` and a version byte,
+    /// followed by the number of ticks. Every tick is the number of reads,
+    /// followed by the reads. Every read is its address, its length, a
+    /// byte that is `1` if the read succeeded and `0` if it failed and,
+    /// for successful reads only, the bytes that were read.
+    /// All numbers are encoded as unsigned LEB128.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&MAGIC);
+        out.push(VERSION);
+        write_varint(&mut out, self.ticks.len() as u64);
+        for tick in &self.ticks {
+            write_varint(&mut out, tick.len() as u64);
+            for read in tick {
+                write_varint(&mut out, read.address);
+                write_varint(&mut out, read.len as u64);
+                match &read.bytes {
+                    Some(bytes) => {
+                        out.push(1);
+                        out.extend_from_slice(bytes);
+                    }
+                    None => out.push(0),
+                }
+            }
+        }
+        out
+    }
+
+    /// Decode a recording from the binary format produced by [`Recording::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut input = Input(bytes);
+        if input.take(MAGIC.len())? != MAGIC {
+            return Err(DecodeError::InvalidMagic);
+        }
+        match input.take(1)?[0] {
+            VERSION => {}
+            version => return Err(DecodeError::UnsupportedVersion(version)),
+        }
+
+        let ticks = input.varint()?;
+        let mut recording = Recording {
+            ticks: Vec::with_capacity(ticks.min(1024) as usize),
+        };
+        for _ in 0..ticks {
+            let reads = input.varint()?;
+            let mut tick = Vec::with_capacity(reads.min(1024) as usize);
+            for _ in 0..reads {
+                let address = input.varint()?;
+                let len = usize::try_from(input.varint()?).map_err(|_| DecodeError::InvalidData)?;
+                // A read that wraps around the address space is corrupt.
+                address
+                    .checked_add(len as u64)
+                    .ok_or(DecodeError::InvalidData)?;
+                let bytes = match input.take(1)?[0] {
+                    0 => None,
+                    1 => Some(input.take(len)?.to_vec()),
+                    _ => return Err(DecodeError::InvalidData),
+                };
+                tick.push(Read {
+                    address,
+                    len,
+                    bytes,
+                });
+            }
+            recording.ticks.push(tick);
+        }
+
+        if input.0.is_empty() {
+            Ok(recording)
+        } else {
+            Err(DecodeError::InvalidData)
+        }
+    }
+}
+
+/// The reason why a [`Recording`] could not be decoded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// The data does not start with the magic bytes.
+    InvalidMagic,
+    /// The data was written by an unsupported version of the format.
+    UnsupportedVersion(u8),
+    /// The data ended in the middle of a recording.
+    UnexpectedEnd,
+    /// The data is not a valid recording.
+    InvalidData,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidMagic => f.write_str("the data is not a recording"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "the recording version {version} is not supported")
+            }
+            Self::UnexpectedEnd => f.write_str("the recording ended unexpectedly"),
+            Self::InvalidData => f.write_str("the recording contains invalid data"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+/// A [`MemReader`] that serves the reads of a [`Recording`].
+///
+/// Reads are served from the current tick only. A read succeeds if it
+/// is covered by a successful read that was recorded in the tick,
+/// regardless of the order in which the reads happen.
+pub struct Replay {
+    recording: Recording,
+    tick: usize,
+}
+
+impl Replay {
+    /// Start replaying the recording at its first tick.
+    pub const fn new(recording: Recording) -> Self {
+        Self { recording, tick: 0 }
+    }
+
+    /// Returns the index of the tick that is currently replayed.
+    pub const fn tick(&self) -> usize {
+        self.tick
+    }
+
+    /// Advance to the next tick.
+    /// Returns `false` if there are no more ticks to replay.
+    pub fn next_tick(&mut self) -> bool {
+        self.tick += 1;
+        self.tick < self.recording.ticks.len()
+    }
+}
+
+impl MemReader for Replay {
+    fn read<T: CheckedBitPattern, A: Into<Address>>(&self, addr: A) -> Result<T, Error> {
+        let addr = addr.into();
+        crate::read_bits(addr, |buf| {
+            self.read_bytes(addr, buf)
+                .map_err(|_| Error::unreadable::<T>(addr))
+        })
+    }
+
+    fn read_bytes<A: Into<Address>>(&self, addr: A, buf: &mut [u8]) -> Result<(), Error> {
+        let addr = addr.into();
+        let start = addr.value();
+        let end = start.checked_add(buf.len() as u64);
+
+        let bytes = self
+            .recording
+            .ticks
+            .get(self.tick)
+            .into_iter()
+            .flatten()
+            .find_map(|read| {
+                let bytes = read.bytes.as_deref()?;
+                let offset = start.checked_sub(read.address)? as usize;
+                (end? <= read.address.checked_add(read.len as u64)?).then(|| &bytes[offset..])
+            })
+            .ok_or_else(|| Error::unreadable_bytes(addr, buf.len()))?;
+
+        buf.copy_from_slice(&bytes[..buf.len()]);
+        Ok(())
+    }
+
+    fn read_into<T: CheckedBitPattern, A: Into<Address>>(
+        &self,
+        addr: A,
+        buf: &mut [T],
+    ) -> Result<(), Error> {
+        crate::read_into_chunked(self, addr, buf)
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+struct Input<'a>(&'a [u8]);
+
+impl<'a> Input<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if self.0.len() < len {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn varint(&mut self) -> Result<u64, DecodeError> {
+        let mut value = 0_u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= u64::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(DecodeError::InvalidData)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake::FakeHeap;
+
+    fn encode(address: u64, len: u64) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        out.push(VERSION);
+        for value in [1, 1, address, len] {
+            write_varint(&mut out, value);
+        }
+        out.push(0);
+        out
+    }
+
+    #[test]
+    fn round_trip() {
+        let recording = Recording::from_bytes(&encode(0x1000, 8)).unwrap();
+        assert_eq!(recording.ticks(), 1);
+        assert_eq!(recording.reads(0), 1);
+        assert_eq!(recording.to_bytes(), encode(0x1000, 8));
+    }
+
+    #[test]
+    fn reads_that_wrap_around_are_corrupt() {
+        assert_eq!(
+            Recording::from_bytes(&encode(u64::MAX - 3, 8)),
+            Err(DecodeError::InvalidData)
+        );
+        assert!(Recording::from_bytes(&encode(u64::MAX - 8, 8)).is_ok());
+    }
+
+    #[test]
+    fn replays_keep_ticks_apart() {
+        let mut heap = FakeHeap::new();
+        heap.write(0x1000_u64, 1_u32).write(0x2000_u64, 2_u64);
+
+        let mut recorder = Recorder::new(&heap);
+        assert_eq!(recorder.read::<u32, _>(0x1000_u64).unwrap(), 1);
+        assert!(recorder.read::<u32, _>(0x3000_u64).is_err());
+        recorder.next_tick();
+        assert_eq!(recorder.read::<u64, _>(0x2000_u64).unwrap(), 2);
+        let recording = recorder.into_recording();
+        assert_eq!(recording.ticks(), 2);
+        assert_eq!(recording.reads(0), 2);
+        assert_eq!(recording.reads(1), 1);
+
+        let bytes = recording.to_bytes();
+        let decoded = Recording::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, recording);
+
+        let mut replay = Replay::new(decoded);
+        assert_eq!(replay.read::<u32, _>(0x1000_u64).unwrap(), 1);
+        assert_eq!(replay.read::<u16, _>(0x1002_u64).unwrap(), 0);
+        assert!(replay.read::<u32, _>(0x3000_u64).is_err());
+        assert!(replay.read::<u64, _>(0x2000_u64).is_err());
+
+        assert!(replay.next_tick());
+        assert_eq!(replay.tick(), 1);
+        assert_eq!(replay.read::<u64, _>(0x2000_u64).unwrap(), 2);
+        assert!(replay.read::<u32, _>(0x1000_u64).is_err());
+        assert!(!replay.next_tick());
+    }
+
+    #[test]
+    fn partly_covered_reads_are_unreadable() {
+        let mut heap = FakeHeap::new();
+        heap.write(0x1000_u64, 1_u32).write(0x1004_u64, 2_u32);
+
+        let recorder = Recorder::new(&heap);
+        assert_eq!(recorder.read::<u32, _>(0x1000_u64).unwrap(), 1);
+        let replay = Replay::new(recorder.into_recording());
+
+        assert!(replay.read::<u32, _>(0x1002_u64).is_err());
+        assert!(replay.read::<u64, _>(0x1000_u64).is_err());
+        assert!(replay.read::<u32, _>(0x0ffe_u64).is_err());
+    }
+
+    #[test]
+    fn truncated_recordings_end_unexpectedly() {
+        let mut heap = FakeHeap::new();
+        heap.write(0x1000_u64, u32::MAX);
+        let recorder = Recorder::new(&heap);
+        recorder.read::<u32, _>(0x1000_u64).unwrap();
+        let bytes = recorder.into_recording().to_bytes();
+
+        assert!(Recording::from_bytes(&bytes).is_ok());
+        for len in 0..bytes.len() {
+            assert_eq!(
+                Recording::from_bytes(&bytes[..len]),
+                Err(DecodeError::UnexpectedEnd)
+            );
+        }
+    }
+
+    #[test]
+    fn over_long_varints_are_invalid() {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        // Ten bytes with continuation bits carry more than 64 bits.
+        bytes.extend_from_slice(&[0x80; 10]);
+        bytes.push(0);
+        assert_eq!(Recording::from_bytes(&bytes), Err(DecodeError::InvalidData));
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        write_varint(&mut bytes, u64::MAX);
+        assert_eq!(bytes.len(), MAGIC.len() + 1 + 10);
+        assert_eq!(Input(&bytes[MAGIC.len() + 1..]).varint(), Ok(u64::MAX));
+    }
+}
@@ -0,0 +1,273 @@
+//! Instrumentation of memory reads.
+//!
+//! A [`StatsReader`] wraps any [`MemReader`] and counts the reads and the
+//! bytes that are read through it. Reads are grouped by a label, that
+//! can be set to name the binding or call site that is doing them, and
+//! by the name of the type that is read, which makes it possible to find
+//! out which `List` or `Map` iteration is the most expensive one.
+//!
+//! ```
+//! use csharp_mem::{fake::FakeHeap, stats::StatsReader};
+//!
+//! let mut heap = FakeHeap::new();
+//! let scores = heap.list(0x1000, 0x2000, &[10_u32, 20, 30]);
+//! let name = heap.string(0x3000, "Level 1");
+//!
+//! let reader = StatsReader::new(&heap);
+//! reader.set_label("scores");
+//! let total = scores.iter(&reader).unwrap().sum::<Result<u32, _>>();
+//! assert_eq!(total.unwrap(), 60);
+//! reader.set_label("level name");
+//! assert_eq!(name.to_std_string(&reader).unwrap(), "Level 1");
+//!
+//! let summary = reader.summary();
+//! assert!(summary.groups.iter().any(|group| group.label == "scores"));
+//! println!("{summary}");
+//! ```
+
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::{
+    any::type_name,
+    cell::{Cell, RefCell},
+    fmt,
+    mem::{size_of, size_of_val},
+};
+
+use asr::Address;
+use bytemuck::CheckedBitPattern;
+
+use crate::{Backend, BackendReader, Error, MemReader};
+
+/// A [`MemReader`] that counts the reads of the wrapped reader.
+pub struct StatsReader<R> {
+    reader: R,
+    label: Cell<&'static str>,
+    groups: RefCell<BTreeMap<(&'static str, &'static str), Stats>>,
+    addresses: RefCell<BTreeMap<u64, u64>>,
+}
+
+/// Read counts of a [`StatsReader`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of reads.
+    pub reads: u64,
+    /// The number of bytes that have been read.
+    pub bytes: u64,
+    /// The number of reads that failed.
+    pub failed: u64,
+}
+
+/// The reads of a single label and type.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Group {
+    /// The label that was set when the reads happened.
+    pub label: &'static str,
+    /// The name of the type that was read.
+    pub type_name: &'static str,
+    /// The read counts.
+    pub stats: Stats,
+}
+
+/// A summary of the reads of a [`StatsReader`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    /// The read counts of all reads.
+    pub total: Stats,
+    /// The read counts per label and type, the most bytes first.
+    pub groups: Vec<Group>,
+}
+
+impl<R: MemReader> StatsReader<R> {
+    /// Start counting the reads of the given reader.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            label: Cell::new(""),
+            groups: RefCell::new(BTreeMap::new()),
+            addresses: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// Returns the reader whose reads are counted.
+    pub const fn reader(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns the wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Attribute all following reads to the given label,
+    /// e.g. the name of the binding that is doing them.
+    pub fn set_label(&self, label: &'static str) {
+        self.label.set(label);
+    }
+
+    /// Returns the read counts, grouped by label and type.
+    pub fn summary(&self) -> Summary {
+        let mut groups = self
+            .groups
+            .borrow()
+            .iter()
+            .map(|(&(label, type_name), &stats)| Group {
+                label,
+                type_name,
+                stats,
+            })
+            .collect::<Vec<_>>();
+        groups.sort_by_key(|group| core::cmp::Reverse(group.stats.bytes));
+
+        let total = groups.iter().fold(Stats::default(), |total, group| Stats {
+            reads: total.reads + group.stats.reads,
+            bytes: total.bytes + group.stats.bytes,
+            failed: total.failed + group.stats.failed,
+        });
+
+        Summary { total, groups }
+    }
+
+    /// Returns the `count` addresses that have been read from the most,
+    /// together with the number of reads, the most reads first.
+    pub fn hot_addresses(&self, count: usize) -> Vec<(Address, u64)> {
+        let mut addresses = self
+            .addresses
+            .borrow()
+            .iter()
+            .map(|(&address, &reads)| (Address::new(address), reads))
+            .collect::<Vec<_>>();
+        addresses.sort_by_key(|&(_, reads)| core::cmp::Reverse(reads));
+        addresses.truncate(count);
+        addresses
+    }
+
+    /// Reset all counts, e.g. at the start of a tick.
+    pub fn reset(&mut self) {
+        self.groups.get_mut().clear();
+        self.addresses.get_mut().clear();
+    }
+
+    fn count<T>(
+        &self,
+        type_name: &'static str,
+        addr: Address,
+        bytes: usize,
+        result: &Result<T, Error>,
+    ) {
+        let mut groups = self.groups.borrow_mut();
+        let stats = groups.entry((self.label.get(), type_name)).or_default();
+        stats.reads += 1;
+        stats.bytes += bytes as u64;
+        stats.failed += u64::from(result.is_err());
+
+        *self.addresses.borrow_mut().entry(addr.value()).or_default() += 1;
+    }
+}
+
+impl<R: MemReader> MemReader for StatsReader<R> {
+    fn read<T: CheckedBitPattern, A: Into<Address>>(&self, addr: A) -> Result<T, Error> {
+        let addr = addr.into();
+        let result = self.reader.read(addr);
+        self.count(type_name::<T>(), addr, size_of::<T>(), &result);
+        result
+    }
+
+    fn read_bytes<A: Into<Address>>(&self, addr: A, buf: &mut [u8]) -> Result<(), Error> {
+        let addr = addr.into();
+        let result = self.reader.read_bytes(addr, buf);
+        self.count(type_name::<[u8]>(), addr, buf.len(), &result);
+        result
+    }
+
+    fn read_into<T: CheckedBitPattern, A: Into<Address>>(
+        &self,
+        addr: A,
+        buf: &mut [T],
+    ) -> Result<(), Error> {
+        let addr = addr.into();
+        let result = self.reader.read_into(addr, buf);
+        self.count(type_name::<[T]>(), addr, size_of_val(buf), &result);
+        result
+    }
+
+    fn max_len(&self) -> usize {
+        self.reader.max_len()
+    }
+}
+
+impl<R: BackendReader> BackendReader for StatsReader<R> {
+    fn backend(&self) -> Backend {
+        self.reader.backend()
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} reads, {} bytes, {} failed",
+            self.total.reads, self.total.bytes, self.total.failed
+        )?;
+        for group in &self.groups {
+            let label = if group.label.is_empty() {
+                "-"
+            } else {
+                group.label
+            };
+            writeln!(
+                f,
+                "{label}: {} ({} reads, {} bytes, {} failed)",
+                group.type_name, group.stats.reads, group.stats.bytes, group.stats.failed
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fake::FakeHeap, Entry};
+
+    #[test]
+    fn iterations_are_counted_under_their_element_type() {
+        let mut heap = FakeHeap::new();
+        let scores = heap.list(0x1000_u64, 0x2000_u64, &[10_u32, 20, 30]);
+        let levels = heap.map(0x3000_u64, 0x4000_u64, &[(1_u32, 2_u64)]);
+
+        let reader = StatsReader::new(&heap);
+        reader.set_label("scores");
+        assert_eq!(scores.iter(&reader).unwrap().count(), 3);
+        reader.set_label("levels");
+        assert_eq!(levels.iter(&reader).unwrap().count(), 1);
+
+        let summary = reader.summary();
+        let group = |label, type_name| {
+            summary
+                .groups
+                .iter()
+                .find(|group| group.label == label && group.type_name == type_name)
+                .map(|group| group.stats)
+        };
+        assert_eq!(
+            group("scores", type_name::<[u32]>()),
+            Some(Stats {
+                reads: 1,
+                bytes: 12,
+                failed: 0,
+            })
+        );
+        assert_eq!(
+            group("levels", type_name::<[Entry<u32, u64>]>()),
+            Some(Stats {
+                reads: 1,
+                bytes: size_of::<Entry<u32, u64>>() as u64,
+                failed: 0,
+            })
+        );
+        assert!(summary
+            .groups
+            .iter()
+            .all(|group| group.type_name != type_name::<[u8]>()));
+    }
+}